@@ -2,7 +2,9 @@
 #define TINYBVH_RUST
 
 #include <array>
+#include <cstddef>
 #include <memory>
+#include <type_traits>
 
 #include "rust/cxx.h"
 #include "tinybvh-rs/ffi/tinybvh/tiny_bvh.h"
@@ -10,6 +12,10 @@
 namespace tinybvh {
 
 /* Math */
+
+// `tinybvh_rs::Vec4` is 4 floats.
+static_assert(sizeof(bvhvec4) == 4 * sizeof(float), "bvhvec4 must be 16 bytes");
+
 Ray ray_new(const std::array<float, 3>& origin, const std::array<float, 3>& dir);
 
 /* BVH Wald 32 */
@@ -21,13 +27,57 @@ rust::Slice<const uint32_t> BVH_indices(const BVH&);
 
 /* CWBVH */
 
-struct NodeCWBVH; // TODO: Remove once tinybvh provides a struct for CWBVH node.
+// Node encoded by `BVH8_CWBVH` in `bvh8Data`, mirrored by `tinybvh_rs::cwbvh::Node`.
+struct NodeCWBVH {
+    bvhvec3 min;
+    uint8_t exyz[3];
+    uint8_t imask;
+    uint32_t childBaseIdx;
+    uint32_t primitiveBaseIdx;
+    uint8_t childMeta[8];
+    uint8_t qloX[8], qloY[8], qloZ[8];
+    uint8_t qhiX[8], qhiY[8], qhiZ[8];
+};
+static_assert(sizeof(std::remove_pointer_t<decltype(BVH8_CWBVH::bvh8Data)>) == sizeof(bvhvec4), "CWBVH nodes must be stored in bvhvec4 blocks");
+static_assert(sizeof(NodeCWBVH) == 5 * sizeof(bvhvec4), "CWBVH node must span 5 blocks");
+static_assert(alignof(NodeCWBVH) <= alignof(bvhvec4), "CWBVH node must not be more aligned than its blocks");
+static_assert(offsetof(NodeCWBVH, exyz) == 12, "CWBVH node layout mismatch");
+static_assert(offsetof(NodeCWBVH, imask) == 15, "CWBVH node layout mismatch");
+static_assert(offsetof(NodeCWBVH, childBaseIdx) == 16, "CWBVH node layout mismatch");
+static_assert(offsetof(NodeCWBVH, primitiveBaseIdx) == 20, "CWBVH node layout mismatch");
+static_assert(offsetof(NodeCWBVH, childMeta) == 24, "CWBVH node layout mismatch");
+static_assert(offsetof(NodeCWBVH, qloX) == 32, "CWBVH node layout mismatch");
+static_assert(offsetof(NodeCWBVH, qloY) == 40, "CWBVH node layout mismatch");
+static_assert(offsetof(NodeCWBVH, qloZ) == 48, "CWBVH node layout mismatch");
+static_assert(offsetof(NodeCWBVH, qhiX) == 56, "CWBVH node layout mismatch");
+static_assert(offsetof(NodeCWBVH, qhiY) == 64, "CWBVH node layout mismatch");
+static_assert(offsetof(NodeCWBVH, qhiZ) == 72, "CWBVH node layout mismatch");
+
+// Triangle encoded by `BVH8_CWBVH` in `bvh8Tris`, mirrored by `tinybvh_rs::cwbvh::Primitive`.
+struct PrimitiveCWBVH {
+    bvhvec3 edge1;
+    uint32_t padding0;
+    bvhvec3 edge2;
+    uint32_t padding1;
+    bvhvec3 vertex0;
+    uint32_t originalPrimitive;
+};
+static_assert(sizeof(std::remove_pointer_t<decltype(BVH8_CWBVH::bvh8Tris)>) == sizeof(bvhvec4), "CWBVH primitives must be stored in bvhvec4 blocks");
+static_assert(sizeof(PrimitiveCWBVH) == 3 * sizeof(bvhvec4), "CWBVH primitive must span 3 blocks");
+static_assert(alignof(PrimitiveCWBVH) <= alignof(bvhvec4), "CWBVH primitive must not be more aligned than its blocks");
+static_assert(offsetof(PrimitiveCWBVH, edge2) == 16, "CWBVH primitive layout mismatch");
+static_assert(offsetof(PrimitiveCWBVH, vertex0) == 32, "CWBVH primitive layout mismatch");
+static_assert(offsetof(PrimitiveCWBVH, originalPrimitive) == 44, "CWBVH primitive layout mismatch");
 
 std::unique_ptr<BVH8_CWBVH> CWBVH_new();
-const uint8_t* CWBVH_nodes(const BVH8_CWBVH&);
-uint32_t CWBVH_nodes_count(const BVH8_CWBVH&);
-const uint8_t* CWBVH_primitives(const BVH8_CWBVH&);
-uint32_t CWBVH_primitives_count(const BVH8_CWBVH&);
+rust::Slice<const NodeCWBVH> CWBVH_nodes(const BVH8_CWBVH&);
+uint32_t CWBVH_blocks_count(const BVH8_CWBVH&);
+rust::Slice<const PrimitiveCWBVH> CWBVH_primitives(const BVH8_CWBVH&);
+
+/* BVH4 GPU */
+
+std::unique_ptr<BVH4_GPU> BVH4_GPU_new();
+rust::Slice<const bvhvec4> BVH4_GPU_blocks(const BVH4_GPU&);
 
 }
 
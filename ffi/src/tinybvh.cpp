@@ -24,12 +24,23 @@ rust::Slice<const uint32_t> BVH_indices(const BVH& bvh) {
 /** CWBVH */
 
 std::unique_ptr<BVH8_CWBVH> CWBVH_new() { return std::make_unique<BVH8_CWBVH>(); }
-const uint8_t* CWBVH_nodes(const BVH8_CWBVH& bvh) { return reinterpret_cast<const uint8_t*>(bvh.bvh8Data); }
-uint32_t CWBVH_nodes_count(const BVH8_CWBVH& bvh) {
+rust::Slice<const NodeCWBVH> CWBVH_nodes(const BVH8_CWBVH& bvh) {
+    /* Layout is checked against `NodeCWBVH` in the header, partial nodes are dropped. */
+    return rust::Slice{reinterpret_cast<const NodeCWBVH*>(bvh.bvh8Data), bvh.usedBlocks / 5};
+}
+uint32_t CWBVH_blocks_count(const BVH8_CWBVH& bvh) {
     /* tinybvh `usedBlocks` is the number of `vec4`, **not** the number of nodes. */
-    return bvh.usedBlocks / 5;
+    return bvh.usedBlocks;
+}
+rust::Slice<const PrimitiveCWBVH> CWBVH_primitives(const BVH8_CWBVH& bvh) {
+    return rust::Slice{reinterpret_cast<const PrimitiveCWBVH*>(bvh.bvh8Tris), bvh.idxCount};
+}
+
+/** BVH4 GPU */
+
+std::unique_ptr<BVH4_GPU> BVH4_GPU_new() { return std::make_unique<BVH4_GPU>(); }
+rust::Slice<const bvhvec4> BVH4_GPU_blocks(const BVH4_GPU& bvh) {
+    return rust::Slice{const_cast<const bvhvec4*>(bvh.bvh4Data), bvh.usedBlocks};
 }
-const uint8_t* CWBVH_primitives(const BVH8_CWBVH& bvh) { return reinterpret_cast<const uint8_t*>(bvh.bvh8Tris); }
-uint32_t CWBVH_primitives_count(const BVH8_CWBVH& bvh) { return bvh.idxCount; }
 
 }
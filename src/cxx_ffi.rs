@@ -6,8 +6,8 @@ pub struct Vec4Slice {
     stride: u32,
 }
 
-impl From<pas::Slice<'_, [f32; 4]>> for Vec4Slice {
-    fn from(value: pas::Slice<[f32; 4]>) -> Self {
+impl From<&pas::Slice<'_, [f32; 4]>> for Vec4Slice {
+    fn from(value: &pas::Slice<[f32; 4]>) -> Self {
         Self {
             data: value.as_ptr() as *const i32,
             count: value.len() as u32,
@@ -16,6 +16,16 @@ impl From<pas::Slice<'_, [f32; 4]>> for Vec4Slice {
     }
 }
 
+/// tinybvh `bvhvec4`, the 16 bytes block of the packed layouts.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Vec4(pub [f32; 4]);
+
+// Ensure `bvhvec4` always has a trivial move ctor and no destructor
+unsafe impl cxx::ExternType for Vec4 {
+    type Id = cxx::type_id!("tinybvh::bvhvec4");
+    type Kind = cxx::kind::Trivial;
+}
 // Ensure `bvhvec4slice` always has a trivial move ctor and no destructor
 unsafe impl cxx::ExternType for Vec4Slice {
     type Id = cxx::type_id!("tinybvh::bvhvec4slice");
@@ -36,6 +46,16 @@ unsafe impl cxx::ExternType for crate::wald::Node {
     type Id = cxx::type_id!("tinybvh::BVHNode");
     type Kind = cxx::kind::Trivial;
 }
+// Ensure `NodeCWBVH` always has a trivial move ctor and no destructor
+unsafe impl cxx::ExternType for crate::cwbvh::Node {
+    type Id = cxx::type_id!("tinybvh::NodeCWBVH");
+    type Kind = cxx::kind::Trivial;
+}
+// Ensure `PrimitiveCWBVH` always has a trivial move ctor and no destructor
+unsafe impl cxx::ExternType for crate::cwbvh::Primitive {
+    type Id = cxx::type_id!("tinybvh::PrimitiveCWBVH");
+    type Kind = cxx::kind::Trivial;
+}
 
 #[cxx::bridge(namespace = "tinybvh")]
 pub(crate) mod ffi {
@@ -43,6 +63,7 @@ pub(crate) mod ffi {
         include!("tinybvh-rs/ffi/include/tinybvh.h");
 
         // Utils
+        pub type bvhvec4 = super::Vec4;
         pub type bvhvec4slice = super::Vec4Slice;
         pub type Ray = crate::Ray;
         pub fn ray_new(origin: &[f32; 3], dir: &[f32; 3]) -> Ray;
@@ -63,12 +84,21 @@ pub(crate) mod ffi {
         // CWBVH
         pub type BVH8_CWBVH;
         pub fn CWBVH_new() -> UniquePtr<BVH8_CWBVH>;
-        pub fn CWBVH_nodes(bvh: &BVH8_CWBVH) -> *const u8;
-        pub fn CWBVH_nodes_count(bvh: &BVH8_CWBVH) -> u32;
-        pub fn CWBVH_primitives(bvh: &BVH8_CWBVH) -> *const u8;
-        pub fn CWBVH_primitives_count(bvh: &BVH8_CWBVH) -> u32;
+        pub type NodeCWBVH = crate::cwbvh::Node;
+        pub type PrimitiveCWBVH = crate::cwbvh::Primitive;
+        pub fn CWBVH_nodes(bvh: &BVH8_CWBVH) -> &[NodeCWBVH];
+        pub fn CWBVH_blocks_count(bvh: &BVH8_CWBVH) -> u32;
+        pub fn CWBVH_primitives(bvh: &BVH8_CWBVH) -> &[PrimitiveCWBVH];
         pub fn Build(self: Pin<&mut BVH8_CWBVH>, primitives: &bvhvec4slice);
         pub fn BuildHQ(self: Pin<&mut BVH8_CWBVH>, primitives: &bvhvec4slice);
         pub fn Intersect(self: &BVH8_CWBVH, original: &mut Ray) -> i32;
+
+        // BVH4 GPU
+        pub type BVH4_GPU;
+        pub fn BVH4_GPU_new() -> UniquePtr<BVH4_GPU>;
+        pub fn BVH4_GPU_blocks(bvh: &BVH4_GPU) -> &[bvhvec4];
+        pub fn Build(self: Pin<&mut BVH4_GPU>, primitives: &bvhvec4slice);
+        pub fn BuildHQ(self: Pin<&mut BVH4_GPU>, primitives: &bvhvec4slice);
+        pub fn Intersect(self: &BVH4_GPU, original: &mut Ray) -> i32;
     }
 }
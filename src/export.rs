@@ -0,0 +1,77 @@
+use crate::{Aabb, Visitor};
+use std::{io::Write, ops::RangeBounds};
+
+/// Box faces, counter-clockwise seen from outside.
+///
+/// Corners are indexed by the bits `0bzyx`, selecting min (`0`) or max (`1`) per axis.
+const BOX_FACES: [[usize; 4]; 6] = [
+    [0, 2, 3, 1],
+    [4, 5, 7, 6],
+    [0, 1, 5, 4],
+    [2, 6, 7, 3],
+    [0, 4, 6, 2],
+    [1, 3, 7, 5],
+];
+
+/// Gather node bounds from a [`Visitor`] walk, and write them as OBJ boxes.
+pub(crate) struct ObjExporter<R> {
+    depth_range: R,
+    /// Bounds of the exported nodes, per depth.
+    boxes: Vec<Vec<Aabb>>,
+}
+
+impl<R: RangeBounds<u32>> ObjExporter<R> {
+    pub(crate) fn new(depth_range: R) -> Self {
+        Self {
+            depth_range,
+            boxes: Vec::new(),
+        }
+    }
+
+    /// Write the boxes, with one OBJ object named `depth_<depth>` per depth.
+    pub(crate) fn write<W: Write>(self, mut writer: W) -> std::io::Result<()> {
+        writeln!(writer, "# BVH node bounds")?;
+        let mut vertex_count = 0;
+        for (depth, boxes) in self.boxes.iter().enumerate() {
+            if boxes.is_empty() {
+                continue;
+            }
+            writeln!(writer, "o depth_{depth}")?;
+            for aabb in boxes {
+                for corner in 0..8 {
+                    let [x, y, z] = [0, 1, 2].map(|axis| {
+                        if corner & (1 << axis) == 0 {
+                            aabb.min[axis]
+                        } else {
+                            aabb.max[axis]
+                        }
+                    });
+                    writeln!(writer, "v {x} {y} {z}")?;
+                }
+                for [a, b, c, d] in BOX_FACES.map(|face| face.map(|i| vertex_count + i + 1)) {
+                    writeln!(writer, "f {a} {b} {c} {d}")?;
+                }
+                vertex_count += 8;
+            }
+        }
+        writer.flush()
+    }
+}
+
+impl<R: RangeBounds<u32>> Visitor for ObjExporter<R> {
+    fn enter_node(&mut self, depth: u32, aabb: &Aabb, _is_leaf: bool) -> bool {
+        if self.depth_range.contains(&depth) {
+            let depth = depth as usize;
+            if self.boxes.len() <= depth {
+                self.boxes.resize(depth + 1, Vec::new());
+            }
+            self.boxes[depth].push(*aabb);
+        }
+        // Skip subtrees deeper than the range.
+        match self.depth_range.end_bound() {
+            std::ops::Bound::Included(&end) => depth < end,
+            std::ops::Bound::Excluded(&end) => depth + 1 < end,
+            std::ops::Bound::Unbounded => true,
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{math, InstrumentedIntersector, Ray, TraversalStats};
+
+/// Pinhole camera, used by [`heatmap`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    /// Eye position.
+    pub position: [f32; 3],
+    /// Point looked at.
+    ///
+    /// The camera looks along `-Z` if equal to [`Camera::position`].
+    pub target: [f32; 3],
+    /// Up direction, doesn't need to be orthogonal to the view direction.
+    ///
+    /// Replaced by the axis the least aligned with the view direction if
+    /// parallel to it, e.g., when looking straight down with `+Y` up.
+    pub up: [f32; 3],
+    /// Vertical field of view, in radians.
+    pub fov_y: f32,
+}
+
+impl Camera {
+    /// Create a camera looking at `target`, with `+Y` up and a 60° vertical field of view.
+    pub fn new(position: [f32; 3], target: [f32; 3]) -> Self {
+        Self {
+            position,
+            target,
+            up: [0.0, 1.0, 0.0],
+            fov_y: 60.0_f32.to_radians(),
+        }
+    }
+
+    /// Primary ray going through the center of pixel `(x, y)`.
+    ///
+    /// Pixel `(0, 0)` is the top left corner of the image.
+    pub fn ray(&self, x: u32, y: u32, width: u32, height: u32) -> Ray {
+        let (forward, right, up) = self.basis();
+
+        let half_height = (self.fov_y * 0.5).tan();
+        let half_width = half_height * width as f32 / height as f32;
+        let u = ((x as f32 + 0.5) / width as f32 * 2.0 - 1.0) * half_width;
+        let v = (1.0 - (y as f32 + 0.5) / height as f32 * 2.0) * half_height;
+        let dir = math::add(
+            forward,
+            math::add(math::scale(right, u), math::scale(up, v)),
+        );
+        Ray::new(self.position, dir)
+    }
+
+    /// Orthonormal forward, right and up directions.
+    fn basis(&self) -> ([f32; 3], [f32; 3], [f32; 3]) {
+        let view = math::sub(self.target, self.position);
+        let forward = if math::length_squared(view) > 0.0 {
+            math::normalize(view)
+        } else {
+            [0.0, 0.0, -1.0]
+        };
+        let mut right = math::cross(forward, self.up);
+        if math::length_squared(right) <= f32::EPSILON * math::length_squared(self.up) {
+            let axis = (0..3)
+                .min_by(|&a, &b| forward[a].abs().total_cmp(&forward[b].abs()))
+                .unwrap();
+            let mut up = [0.0; 3];
+            up[axis] = 1.0;
+            right = math::cross(forward, up);
+        }
+        let right = math::normalize(right);
+        (forward, right, math::cross(right, forward))
+    }
+}
+
+/// Traversal counters for each pixel of an image.
+///
+/// Created with [`heatmap`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Heatmap {
+    /// Image width, in pixels.
+    pub width: u32,
+    /// Image height, in pixels.
+    pub height: u32,
+    /// Counters, row by row, starting from the top left corner.
+    pub pixels: Vec<TraversalStats>,
+}
+
+impl Heatmap {
+    /// Highest [`TraversalStats::cost`] of the image.
+    pub fn max_cost(&self) -> u32 {
+        self.pixels
+            .iter()
+            .map(TraversalStats::cost)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Render the heatmap into an RGBA8 image.
+    ///
+    /// Costs are normalized by `max_cost`, or by [`Self::max_cost`] if `None`,
+    /// and mapped from blue (cheap) to green and red (expensive).
+    pub fn to_rgba8(&self, max_cost: Option<u32>) -> Vec<u8> {
+        let max_cost = max_cost.unwrap_or_else(|| self.max_cost()).max(1) as f32;
+        self.pixels
+            .iter()
+            .flat_map(|pixel| {
+                let t = (pixel.cost() as f32 / max_cost).min(1.0);
+                let [r, g, b] = if t < 0.5 {
+                    [0.0, t * 2.0, 1.0 - t * 2.0]
+                } else {
+                    [t * 2.0 - 1.0, 2.0 - t * 2.0, 0.0]
+                };
+                [r, g, b]
+                    .map(|c| (c * 255.0).round() as u8)
+                    .into_iter()
+                    .chain([255])
+            })
+            .collect()
+    }
+}
+
+/// Intersect a primary ray per pixel, and gather the traversal counters.
+///
+/// # Examples
+///
+/// ```
+/// use tinybvh_rs::{heatmap, wald, Camera};
+///
+/// let triangles = vec![
+///     [-1.0, 1.0, 0.0, 0.0],
+///     [1.0, 1.0, 0.0, 0.0],
+///     [-1.0, 0.0, 0.0, 0.0]
+/// ];
+/// let bvh = wald::BVH::new(&triangles);
+/// let camera = Camera::new([0.0, 0.5, 3.0], [0.0, 0.5, 0.0]);
+/// let image = heatmap(&bvh, &camera, 64, 64).to_rgba8(None);
+/// assert_eq!(image.len(), 64 * 64 * 4);
+/// ```
+pub fn heatmap<B: InstrumentedIntersector>(
+    bvh: &B,
+    camera: &Camera,
+    width: u32,
+    height: u32,
+) -> Heatmap {
+    let pixels = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| bvh.intersect_instrumented(&mut camera.ray(x, y, width, height)))
+        .collect();
+    Heatmap {
+        width,
+        height,
+        pixels,
+    }
+}
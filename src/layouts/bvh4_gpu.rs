@@ -0,0 +1,297 @@
+use crate::{
+    ffi, math,
+    stats::{self, StatsBuilder},
+    traversal::{traverse_wide, WideChild},
+    Aabb, QueryOptions, QueryResult, Ray, Stats, Visitor,
+};
+
+/// Flag set in [`Node::child_info`] for leaf children.
+const LEAF_FLAG: u32 = 0x80000000;
+
+/// Compact 64-bytes 4-wide node layout, designed for mobile GPUs.
+///
+/// Children bounds are quantized to 8 bits per axis, relative to the node bounds.
+///
+/// Node layout used by [`BVH`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Node {
+    /// AABB min position.
+    pub min: [f32; 3],
+    /// Quantized children AABB min, x axis.
+    pub qlox: [u8; 4],
+    /// AABB extent.
+    pub extent: [f32; 3],
+    /// Quantized children AABB max, x axis.
+    pub qhix: [u8; 4],
+    /// Quantized children AABB min, y axis.
+    pub qloy: [u8; 4],
+    /// Quantized children AABB max, y axis.
+    pub qhiy: [u8; 4],
+    /// Quantized children AABB min, z axis.
+    pub qloz: [u8; 4],
+    /// Quantized children AABB max, z axis.
+    pub qhiz: [u8; 4],
+    /// Children data:
+    /// - `0`: Empty slot
+    /// - Most significant bit set: Leaf, with 15 bits triangle count and 16 bits
+    ///   triangle block offset, relative to the node block offset
+    /// - Otherwise: Block offset of the child node
+    pub child_info: [u32; 4],
+}
+
+// A node spans 4 tinybvh `bvhvec4` blocks.
+const _: () = assert!(std::mem::size_of::<Node>() == 4 * 16);
+
+impl Node {
+    /// Returns `true` if child `slot` is unused.
+    pub fn is_empty(&self, slot: usize) -> bool {
+        self.child_info[slot] == 0
+    }
+
+    /// Returns `true` if child `slot` is a leaf.
+    pub fn is_leaf(&self, slot: usize) -> bool {
+        self.child_info[slot] & LEAF_FLAG != 0
+    }
+
+    /// Block offset of child `slot` node.
+    ///
+    /// Only valid for internal children.
+    pub fn child_offset(&self, slot: usize) -> u32 {
+        self.child_info[slot]
+    }
+
+    /// Triangle count and first triangle block offset of leaf child `slot`.
+    ///
+    /// The offset is relative to the node block offset.
+    pub fn leaf_triangles(&self, slot: usize) -> (u32, u32) {
+        let info = self.child_info[slot];
+        ((info >> 16) & 0x7fff, info & 0xffff)
+    }
+
+    /// Dequantized AABB of child `slot`.
+    pub fn child_aabb(&self, slot: usize) -> Aabb {
+        let scale = math::scale(self.extent, 1.0 / 255.0);
+        let lo = [self.qlox[slot], self.qloy[slot], self.qloz[slot]];
+        let hi = [self.qhix[slot], self.qhiy[slot], self.qhiz[slot]];
+        let dequantize = |q: [u8; 3]| [0, 1, 2].map(|i| self.min[i] + q[i] as f32 * scale[i]);
+        Aabb::new(dequantize(lo), dequantize(hi))
+    }
+}
+
+/// Triangle stored inline in the [`BVH`] blocks, spanning 3 blocks.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Triangle {
+    pub edge_1: [f32; 3],
+    pub padding_0: u32,
+    pub edge_2: [f32; 3],
+    pub padding_1: u32,
+    pub vertex_0: [f32; 3],
+    pub original_primitive: u32,
+}
+
+// A triangle spans 3 tinybvh `bvhvec4` blocks.
+const _: () = assert!(std::mem::size_of::<Triangle>() == 3 * 16);
+
+impl Triangle {
+    /// Triangle vertices, in the original winding order.
+    pub fn vertices(&self) -> [[f32; 3]; 3] {
+        [
+            self.vertex_0,
+            math::add(self.vertex_0, self.edge_1),
+            math::add(self.vertex_0, self.edge_2),
+        ]
+    }
+}
+
+/// Depth first iterator over the nodes of a [`BVH`].
+///
+/// Created with [`BVH::nodes`].
+pub struct Nodes<'b> {
+    blocks: &'b [[f32; 4]],
+    stack: Vec<u32>,
+}
+
+impl<'b> Iterator for Nodes<'b> {
+    /// Node block offset and node.
+    type Item = (u32, &'b Node);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.stack.pop()?;
+        let node = node_at(self.blocks, offset);
+        for slot in (0..4).rev() {
+            if !node.is_empty(slot) && !node.is_leaf(slot) {
+                self.stack.push(node.child_offset(slot));
+            }
+        }
+        Some((offset, node))
+    }
+}
+
+/// Compact 4-wide GPU BVH with node layout [`Node`].
+///
+/// Nodes and triangles are packed in a single array of 16 bytes blocks,
+/// with the root node at block `0`.
+pub struct BVH<'a> {
+    inner: cxx::UniquePtr<ffi::BVH4_GPU>,
+    positions: crate::Positions<'a>,
+    /// Built with `build_hq`, which can use spatial splits.
+    spatial_splits: bool,
+}
+
+impl<'a> BVH<'a> {
+    /// Packed nodes and triangles, ready to upload to the GPU.
+    pub fn blocks(&self) -> &[[f32; 4]] {
+        bytemuck::cast_slice(ffi::BVH4_GPU_blocks(&self.inner))
+    }
+
+    /// Node starting at block `offset`.
+    pub fn node(&self, offset: u32) -> &Node {
+        node_at(self.blocks(), offset)
+    }
+
+    /// Triangle starting at block `offset`.
+    pub fn triangle(&self, offset: u32) -> &Triangle {
+        let start = offset as usize;
+        bytemuck::from_bytes(bytemuck::cast_slice(&self.blocks()[start..start + 3]))
+    }
+
+    /// Iterate over the nodes, depth first, starting from the root.
+    pub fn nodes(&self) -> Nodes<'_> {
+        let blocks = self.blocks();
+        let stack = if blocks.is_empty() { vec![] } else { vec![0] };
+        Nodes { blocks, stack }
+    }
+
+    /// Walk the BVH depth first, starting from the root.
+    ///
+    /// Children are visited in slot order, and leaf children are entered as leaf nodes.
+    /// Node bounds are the dequantized bounds stored in the parent, except for
+    /// the root which uses the union of its children bounds.
+    pub fn walk<V: Visitor>(&self, visitor: &mut V) {
+        if self.blocks().is_empty() {
+            return;
+        }
+        let root = self.node(0);
+        let aabb = (0..4)
+            .filter(|&slot| !root.is_empty(slot))
+            .fold(Aabb::empty(), |aabb, slot| {
+                aabb.union(&root.child_aabb(slot))
+            });
+
+        // Entries are `(node offset, depth, aabb, leaf slot)`.
+        let mut stack = vec![(0, 0, aabb, None)];
+        let mut leaf = Vec::new();
+        while let Some((offset, depth, aabb, slot)) = stack.pop() {
+            let node = self.node(offset);
+            if let Some(slot) = slot {
+                if visitor.enter_node(depth, &aabb, true) {
+                    let (count, start) = node.leaf_triangles(slot);
+                    leaf.clear();
+                    leaf.extend(
+                        (0..count)
+                            .map(|i| self.triangle(offset + start + i * 3).original_primitive),
+                    );
+                    visitor.leaf(&leaf);
+                }
+                continue;
+            }
+            if !visitor.enter_node(depth, &aabb, false) {
+                continue;
+            }
+            for slot in (0..4).rev().filter(|&slot| !node.is_empty(slot)) {
+                let aabb = node.child_aabb(slot);
+                if node.is_leaf(slot) {
+                    stack.push((offset, depth + 1, aabb, Some(slot)));
+                } else {
+                    stack.push((node.child_offset(slot), depth + 1, aabb, None));
+                }
+            }
+        }
+    }
+
+    /// Quality statistics.
+    pub fn stats(&self) -> Stats {
+        let mut builder = StatsBuilder::default();
+        self.walk(&mut builder);
+        let memory = std::mem::size_of_val(self.blocks());
+        builder.finish(memory)
+    }
+
+    /// Quality statistics, including the expensive [`Stats::epo_cost`].
+    pub fn stats_with_epo(&self) -> Stats {
+        let epo_cost = stats::epo_cost(
+            |builder| self.walk(builder),
+            |prim| super::triangle(&self.positions, prim),
+        );
+        Stats {
+            epo_cost: Some(epo_cost),
+            ..self.stats()
+        }
+    }
+
+    /// Intersect this instance with a ray, using custom query options.
+    ///
+    /// [`Ray::hit`] is mutated with the intersection data.
+    ///
+    /// Returns the number of steps performed, and the hit side.
+    pub fn intersect_with(&self, ray: &mut Ray, options: &QueryOptions) -> QueryResult {
+        let steps = if *options == QueryOptions::default() {
+            crate::intersect_from_t_min(ray, |ray| self.inner.Intersect(ray) as u32)
+        } else {
+            self.traverse(ray, options)
+        };
+        super::query_result(&self.positions, ray, steps)
+    }
+
+    /// Rust-side closest hit traversal, decoding the packed blocks.
+    fn traverse(&self, ray: &mut Ray, options: &QueryOptions) -> u32 {
+        if self.blocks().is_empty() {
+            return 0;
+        }
+        let children = |offset: u32| {
+            let node = self.node(offset);
+            (0..4)
+                .filter(|&slot| !node.is_empty(slot))
+                .map(move |slot| {
+                    let child = if node.is_leaf(slot) {
+                        let (count, start) = node.leaf_triangles(slot);
+                        WideChild::Leaf((offset + start, count))
+                    } else {
+                        WideChild::Internal(node.child_offset(slot))
+                    };
+                    (node.child_aabb(slot), child)
+                })
+        };
+        let stats = traverse_wide(ray, children, |ray, (start, count)| {
+            for i in 0..count {
+                let triangle = self.triangle(start + i * 3);
+                options.intersect_triangle(ray, triangle.vertices(), triangle.original_primitive);
+            }
+            count
+        });
+        stats.node_visits
+    }
+
+    pub fn new_internal() -> Self {
+        Self {
+            inner: ffi::BVH4_GPU_new(),
+            positions: crate::layouts::empty_positions(),
+            spatial_splits: false,
+        }
+    }
+}
+super::impl_bvh!(BVH, BVH4_GPU);
+
+impl crate::Intersector for BVH<'_> {
+    fn intersect(&self, ray: &mut crate::Ray) -> u32 {
+        crate::intersect_from_t_min(ray, |ray| self.inner.Intersect(ray) as u32)
+    }
+}
+
+/// Node starting at block `offset`.
+fn node_at(blocks: &[[f32; 4]], offset: u32) -> &Node {
+    let start = offset as usize;
+    bytemuck::from_bytes(bytemuck::cast_slice(&blocks[start..start + 4]))
+}
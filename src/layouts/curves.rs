@@ -0,0 +1,112 @@
+use super::custom::{self, Primitive};
+use crate::{math, Aabb, Intersection, Positions, Ray};
+
+/// Linear round curve primitive of a [`BVH`], used for hair and fur.
+///
+/// Each segment is made of 2 consecutive control points, with the radius
+/// stored in the 4th component: `[x, y, z, radius]`. A segment is a
+/// cone with rounded caps, interpolating the radius between its ends.
+///
+/// [`Intersection::prim`] is the segment index, and [`Intersection::u`]
+/// the curve parameter along the segment, in `[0, 1]`.
+/// [`Intersection::v`] is unused.
+pub struct Segment;
+
+impl Primitive for Segment {
+    const POSITIONS: usize = 2;
+
+    fn aabb(positions: &Positions, prim: u32) -> Aabb {
+        let mut aabb = Aabb::empty();
+        for [x, y, z, r] in segment(positions, prim) {
+            aabb.grow([x - r, y - r, z - r]);
+            aabb.grow([x + r, y + r, z + r]);
+        }
+        aabb
+    }
+
+    fn intersect(positions: &Positions, ray: &mut Ray, prim: u32) {
+        let [a, b] = segment(positions, prim);
+        if let Some((t, u)) = intersect_round_cone(ray, a, b) {
+            if t > ray.t_min() && t < ray.hit.t {
+                ray.hit = Intersection { t, u, v: 0.0, prim };
+            }
+        }
+    }
+}
+
+/// BVH over linear round curves, see [`Segment`].
+///
+/// # Examples
+///
+/// ```
+/// use tinybvh_rs::{curves, Intersector, Ray};
+///
+/// let segments = vec![
+///     [0.0, 0.0, 0.0, 0.1],
+///     [0.0, 1.0, 0.0, 0.05],
+/// ];
+/// let bvh = curves::BVH::new(&segments);
+///
+/// let mut ray = Ray::new([0.0, 0.5, 1.0], [0.0, 0.0, -1.0]);
+/// bvh.intersect(&mut ray);
+/// println!("Hit distance: {}", ray.hit.t); // ~0.925
+/// ```
+pub type BVH<'a> = custom::BVH<'a, Segment>;
+
+/// Control points of segment `prim`.
+fn segment(positions: &Positions, prim: u32) -> [[f32; 4]; 2] {
+    let start = prim as usize * 2;
+    [positions[start], positions[start + 1]]
+}
+
+/// Ray / rounded cone intersection.
+///
+/// Returns the entry distance, and the parameter along the segment.
+///
+/// From "Rounded Cone - Intersection", Quilez.
+fn intersect_round_cone(ray: &Ray, a: [f32; 4], b: [f32; 4]) -> Option<(f32, f32)> {
+    let len = math::length(ray.dir);
+    let rd = math::scale(ray.dir, 1.0 / len);
+    let (pa, ra) = ([a[0], a[1], a[2]], a[3]);
+    let (pb, rb) = ([b[0], b[1], b[2]], b[3]);
+
+    let ba = math::sub(pb, pa);
+    let oa = math::sub(ray.origin, pa);
+    let ob = math::sub(ray.origin, pb);
+    let rr = ra - rb;
+    let m0 = math::dot(ba, ba);
+    let m1 = math::dot(ba, oa);
+    let m2 = math::dot(ba, rd);
+    let m3 = math::dot(rd, oa);
+    let m5 = math::dot(oa, oa);
+    let m6 = math::dot(ob, rd);
+    let m7 = math::dot(ob, ob);
+
+    // Body.
+    let d2 = m0 - rr * rr;
+    let k2 = d2 - m2 * m2;
+    let k1 = d2 * m3 - m1 * m2 + m2 * rr * ra;
+    let k0 = d2 * m5 - m1 * m1 + m1 * rr * ra * 2.0 - m0 * ra * ra;
+    let h = k1 * k1 - k0 * k2;
+    if h < 0.0 {
+        return None;
+    }
+    let t = (-h.sqrt() - k1) / k2;
+    let y = m1 - ra * rr + t * m2;
+    if y > 0.0 && y < d2 {
+        let u = (m1 + t * m2) / m0;
+        return Some((t / len, u.clamp(0.0, 1.0)));
+    }
+
+    // Caps.
+    let h1 = m3 * m3 - m5 + ra * ra;
+    let h2 = m6 * m6 - m7 + rb * rb;
+    let cap_a = (h1 > 0.0).then(|| -m3 - h1.sqrt());
+    let cap_b = (h2 > 0.0).then(|| -m6 - h2.sqrt());
+    match (cap_a, cap_b) {
+        (Some(t_a), Some(t_b)) if t_b < t_a => Some((t_b / len, 1.0)),
+        (Some(t_a), _) => Some((t_a / len, 0.0)),
+        (None, Some(t_b)) => Some((t_b / len, 1.0)),
+        (None, None) => None,
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{wald, Aabb, Positions, Ray, Stats, Visitor};
+use std::marker::PhantomData;
+
+/// Maximum number of primitives per leaf.
+const MAX_LEAF_SIZE: usize = 4;
+
+/// Primitive type of a [`BVH`].
+///
+/// Only the primitive specific parts are implemented: bounds and
+/// ray intersection. Primitives are read from [`Positions`], with
+/// [`Primitive::POSITIONS`] consecutive positions per primitive.
+pub trait Primitive {
+    /// Number of positions per primitive.
+    const POSITIONS: usize;
+
+    /// Bounds of primitive `prim`.
+    fn aabb(positions: &Positions, prim: u32) -> Aabb;
+
+    /// Intersect primitive `prim` with a ray.
+    ///
+    /// Responsible for updating [`Ray::hit`] with hits in `]t_min, hit.t[`.
+    fn intersect(positions: &Positions, ray: &mut Ray, prim: u32);
+}
+
+/// BVH over custom primitives, built and traversed on the Rust side.
+///
+/// Nodes use the [`wald::Node`] layout.
+///
+/// See [`crate::quads`], [`crate::curves`] and [`crate::spheres`] for
+/// the available primitive types.
+pub struct BVH<'a, P> {
+    positions: Positions<'a>,
+    nodes: Vec<wald::Node>,
+    indices: Vec<u32>,
+    primitive: PhantomData<P>,
+}
+
+impl<'a, P: Primitive> BVH<'a, P> {
+    /// Create a new BVH from a strided slice of positions.
+    ///
+    /// # Notes
+    ///
+    /// The `primitives` slice must contain [`Primitive::POSITIONS`] positions per primitive.
+    pub fn new<S: Into<Positions<'a>>>(primitives: S) -> Self {
+        let positions = primitives.into();
+        if positions.len() % P::POSITIONS != 0 {
+            panic!(
+                "primitives slice must contain {} positions per primitive",
+                P::POSITIONS
+            )
+        }
+        let aabbs: Vec<Aabb> = (0..(positions.len() / P::POSITIONS) as u32)
+            .map(|prim| P::aabb(&positions, prim))
+            .collect();
+        let (nodes, indices) = wald::build_nodes(&aabbs, MAX_LEAF_SIZE);
+        Self {
+            positions,
+            nodes,
+            indices,
+            primitive: PhantomData,
+        }
+    }
+
+    /// Positions used to build the BVH.
+    pub fn positions(&self) -> &Positions<'a> {
+        &self.positions
+    }
+
+    /// BVH nodes.
+    pub fn nodes(&self) -> &[wald::Node] {
+        &self.nodes
+    }
+
+    /// BVH indices.
+    ///
+    /// Map from leaf entry to primitive index.
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Walk the BVH depth first, starting from the root.
+    ///
+    /// Left children are visited before right children.
+    pub fn walk<V: Visitor>(&self, visitor: &mut V) {
+        wald::walk(&self.nodes, &self.indices, visitor);
+    }
+
+    /// Quality statistics.
+    pub fn stats(&self) -> Stats {
+        wald::stats(&self.nodes, &self.indices)
+    }
+}
+
+impl<P: Primitive> crate::Intersector for BVH<'_, P> {
+    fn intersect(&self, ray: &mut Ray) -> u32 {
+        wald::traverse(&self.nodes, &self.indices, ray, |ray, prim| {
+            P::intersect(&self.positions, ray, prim)
+        })
+    }
+}
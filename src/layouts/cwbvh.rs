@@ -1,5 +1,12 @@
-use crate::ffi;
-use std::{fmt::Debug, marker::PhantomData};
+use crate::{
+    export::ObjExporter,
+    ffi, math,
+    stats::{self, StatsBuilder},
+    traversal::{traverse_wide, WideChild},
+    validation::Validator,
+    Aabb, QueryOptions, QueryResult, Ray, Stats, TraversalStats, ValidationError, Visitor,
+};
+use std::fmt::Debug;
 
 pub struct PrimitiveIter {
     primitive_base_index: u32,
@@ -44,6 +51,48 @@ impl Iterator for PrimitiveIter {
     }
 }
 
+/// Decoded child slot of a [`Node`].
+///
+/// Created with [`Node::children`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Child {
+    /// Unused slot.
+    Empty,
+    /// Internal child node.
+    Internal {
+        /// Index in [`BVH::nodes`].
+        node_index: u32,
+        /// Decompressed child bounds.
+        aabb: Aabb,
+    },
+    /// Leaf child.
+    Leaf {
+        /// Range in [`BVH::primitives`].
+        primitives: std::ops::Range<u32>,
+        /// Decompressed child bounds.
+        aabb: Aabb,
+    },
+}
+
+/// Iterator over the 8 child slots of a [`Node`].
+pub struct ChildIter<'b> {
+    node: &'b Node,
+    slot: usize,
+}
+
+impl Iterator for ChildIter<'_> {
+    type Item = Child;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.slot >= self.node.child_meta.len() {
+            return None;
+        }
+        let slot = self.slot;
+        self.slot += 1;
+        Some(self.node.child(slot))
+    }
+}
+
 /// Format specified in:
 /// "Efficient Incoherent Ray Traversal on GPUs Through Compressed Wide BVHs", Ylitie et al. 2017.
 ///
@@ -77,6 +126,28 @@ pub struct Node {
     pub qhi_z: [u8; 8],
 }
 
+/// Number of 16 bytes blocks per [`Node`].
+const NODE_BLOCKS: u32 = 5;
+
+// A node spans 5 tinybvh `bvhvec4` blocks, and must not require more than
+// the blocks alignment. Offsets match the `NodeCWBVH` asserts of the C++ side.
+const _: () = assert!(std::mem::size_of::<Node>() == NODE_BLOCKS as usize * 16);
+const _: () = assert!(std::mem::align_of::<Node>() <= 16);
+const _: () = {
+    use std::mem::offset_of;
+    assert!(offset_of!(Node, exyz) == 12);
+    assert!(offset_of!(Node, imask) == 15);
+    assert!(offset_of!(Node, child_base_idx) == 16);
+    assert!(offset_of!(Node, primitive_base_idx) == 20);
+    assert!(offset_of!(Node, child_meta) == 24);
+    assert!(offset_of!(Node, qlo_x) == 32);
+    assert!(offset_of!(Node, qlo_y) == 40);
+    assert!(offset_of!(Node, qlo_z) == 48);
+    assert!(offset_of!(Node, qhi_x) == 56);
+    assert!(offset_of!(Node, qhi_y) == 64);
+    assert!(offset_of!(Node, qhi_z) == 72);
+};
+
 impl Node {
     /// Returns `true` if the node is a leaf.
     pub fn is_leaf(&self) -> bool {
@@ -89,6 +160,90 @@ impl Node {
         }
         PrimitiveIter::new(self.primitive_base_idx, self.child_meta)
     }
+
+    /// Decoded child slots, in slot order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tinybvh_rs::cwbvh;
+    ///
+    /// let triangles = vec![
+    ///     [-1.0, 1.0, 0.0, 0.0],
+    ///     [1.0, 1.0, 0.0, 0.0],
+    ///     [-1.0, 0.0, 0.0, 0.0]
+    /// ];
+    /// let bvh = cwbvh::BVH::new(&triangles);
+    /// for child in bvh.nodes()[0].children() {
+    ///     if let cwbvh::Child::Leaf { primitives, aabb } = child {
+    ///         println!("Leaf {:?} in {:?}", primitives, aabb);
+    ///     }
+    /// }
+    /// ```
+    pub fn children(&self) -> ChildIter<'_> {
+        ChildIter {
+            node: self,
+            slot: 0,
+        }
+    }
+
+    /// Decoded child `slot`.
+    pub fn child(&self, slot: usize) -> Child {
+        if self.child_meta[slot] == 0 {
+            Child::Empty
+        } else if self.is_internal(slot) {
+            Child::Internal {
+                node_index: self.child_index(slot),
+                aabb: self.child_aabb(slot),
+            }
+        } else {
+            Child::Leaf {
+                primitives: self.child_primitives(slot),
+                aabb: self.child_aabb(slot),
+            }
+        }
+    }
+
+    /// Largest child bounds quantization step.
+    pub(crate) fn quantization_step(&self) -> f32 {
+        let scale = self.exyz.map(|e| f32::from_bits((e as u32) << 23));
+        scale[0].max(scale[1]).max(scale[2])
+    }
+
+    /// Returns `true` if child `slot` is an internal node.
+    pub(crate) fn is_internal(&self, slot: usize) -> bool {
+        self.imask & (1 << slot) != 0
+    }
+
+    /// Index of the internal child `slot`.
+    ///
+    /// Internal children are stored contiguously, in slot order.
+    pub(crate) fn child_index(&self, slot: usize) -> u32 {
+        let preceding = self.imask & ((1_u16 << slot) - 1) as u8;
+        // Saturate so that corrupted indices stay out of bounds.
+        self.child_base_idx.saturating_add(preceding.count_ones())
+    }
+
+    /// Range in [`BVH::primitives`] of the leaf child `slot`.
+    pub(crate) fn child_primitives(&self, slot: usize) -> std::ops::Range<u32> {
+        let meta = self.child_meta[slot];
+        let start = self
+            .primitive_base_idx
+            .saturating_add((meta & 0b00011111) as u32);
+        start..start.saturating_add((meta & 0b11100000).count_ones())
+    }
+
+    /// Decompressed bounds of child `slot`.
+    pub(crate) fn child_aabb(&self, slot: usize) -> Aabb {
+        // Exponents are stored as the biased exponent of a power of two.
+        let scale = self.exyz.map(|e| f32::from_bits((e as u32) << 23));
+        let lo = [self.qlo_x[slot], self.qlo_y[slot], self.qlo_z[slot]];
+        let hi = [self.qhi_x[slot], self.qhi_y[slot], self.qhi_z[slot]];
+        Aabb::new(
+            [0, 1, 2].map(|i| self.min[i] + lo[i] as f32 * scale[i]),
+            [0, 1, 2].map(|i| self.min[i] + hi[i] as f32 * scale[i]),
+        )
+    }
 }
 
 /// Custom primitive used by [`BVH`].
@@ -103,6 +258,25 @@ pub struct Primitive {
     pub original_primitive: u32,
 }
 
+// A primitive spans 3 tinybvh `bvhvec4` blocks. Offsets match the
+// `PrimitiveCWBVH` asserts of the C++ side.
+const _: () = assert!(std::mem::size_of::<Primitive>() == 3 * 16);
+const _: () = {
+    use std::mem::offset_of;
+    assert!(offset_of!(Primitive, edge_2) == 16);
+    assert!(offset_of!(Primitive, vertex_0) == 32);
+    assert!(offset_of!(Primitive, original_primitive) == 44);
+};
+
+impl Primitive {
+    /// Triangle vertices, in the original winding order.
+    pub fn vertices(&self) -> [[f32; 3]; 3] {
+        // tinybvh stores `edge_1 = v2 - v0` and `edge_2 = v1 - v0`.
+        let v0 = self.vertex_0;
+        [v0, math::add(v0, self.edge_2), math::add(v0, self.edge_1)]
+    }
+}
+
 impl Debug for Primitive {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("cwbvh::Primitive")
@@ -114,18 +288,57 @@ impl Debug for Primitive {
     }
 }
 
+/// Error returned when tinybvh node data doesn't match [`Node`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayoutError {
+    /// Block count isn't a multiple of the node size.
+    BlockCount(u32),
+}
+
+impl std::fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BlockCount(count) => write!(
+                f,
+                "tinybvh CWBVH block count {count} isn't a multiple of {NODE_BLOCKS}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
 /// CWBVH with node layout [`Node`].
 pub struct BVH<'a> {
     inner: cxx::UniquePtr<ffi::BVH8_CWBVH>,
-    _phantom: PhantomData<&'a [f32; 4]>,
+    positions: crate::Positions<'a>,
+    /// Built with `build_hq`, which can use spatial splits.
+    spatial_splits: bool,
 }
 
 impl<'a> BVH<'a> {
+    /// BVH nodes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tinybvh node data is invalid, see [`Self::try_nodes`].
     pub fn nodes(&self) -> &[Node] {
-        // TODO: Create CWBVH node in tinybvh to avoid that.
-        let ptr = ffi::CWBVH_nodes(&self.inner) as *const Node;
-        let count = ffi::CWBVH_nodes_count(&self.inner);
-        unsafe { std::slice::from_raw_parts(ptr, count as usize) }
+        match self.try_nodes() {
+            Ok(nodes) => nodes,
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    /// BVH nodes, validated against the tinybvh layout.
+    ///
+    /// Checks that the block count is a multiple of the node size. The node
+    /// layout is checked at compile time, on both the Rust and C++ sides.
+    pub fn try_nodes(&self) -> Result<&[Node], LayoutError> {
+        let blocks = ffi::CWBVH_blocks_count(&self.inner);
+        if !blocks.is_multiple_of(NODE_BLOCKS) {
+            return Err(LayoutError::BlockCount(blocks));
+        }
+        Ok(ffi::CWBVH_nodes(&self.inner))
     }
 
     /// Encoded primitive data.
@@ -133,17 +346,228 @@ impl<'a> BVH<'a> {
     /// This layout is intersected using a custom primitive array
     /// instead of the original list used during building.
     pub fn primitives(&self) -> &[Primitive] {
-        // TODO: Create struct in tinybvh to avoid that.
-        let ptr = ffi::CWBVH_primitives(&self.inner) as *const Primitive;
-        let count = ffi::CWBVH_primitives_count(&self.inner);
-        unsafe { std::slice::from_raw_parts(ptr, count as usize) }
+        ffi::CWBVH_primitives(&self.inner)
+    }
+
+    /// Intersect this instance with a ray, using custom query options.
+    ///
+    /// [`Ray::hit`] is mutated with the intersection data.
+    ///
+    /// Returns the number of steps performed, and the hit side.
+    pub fn intersect_with(&self, ray: &mut Ray, options: &QueryOptions) -> QueryResult {
+        let steps = if *options == QueryOptions::default() {
+            crate::intersect_from_t_min(ray, |ray| self.inner.Intersect(ray) as u32)
+        } else {
+            self.traverse(ray, options).node_visits
+        };
+        super::query_result(&self.positions, ray, steps)
+    }
+
+    /// Walk the BVH depth first, starting from the root.
+    ///
+    /// Children are visited in slot order, and leaf children are entered as leaf nodes.
+    /// Node bounds are the decompressed bounds stored in the parent, except for
+    /// the root which uses the union of its children bounds.
+    pub fn walk<V: Visitor>(&self, visitor: &mut V) {
+        let nodes = self.nodes();
+        let primitives = self.primitives();
+        if nodes.is_empty() || primitives.is_empty() {
+            return;
+        }
+        let root = Child::Internal {
+            node_index: 0,
+            aabb: nodes[0]
+                .children()
+                .fold(Aabb::empty(), |aabb, child| match child {
+                    Child::Empty => aabb,
+                    Child::Internal { aabb: child, .. } | Child::Leaf { aabb: child, .. } => {
+                        aabb.union(&child)
+                    }
+                }),
+        };
+
+        let mut stack = vec![(root, 0)];
+        let mut leaf = Vec::new();
+        while let Some((child, depth)) = stack.pop() {
+            match child {
+                Child::Empty => {}
+                Child::Leaf {
+                    primitives: range,
+                    aabb,
+                } => {
+                    if visitor.enter_node(depth, &aabb, true) {
+                        leaf.clear();
+                        leaf.extend(range.map(|i| primitives[i as usize].original_primitive));
+                        visitor.leaf(&leaf);
+                    }
+                }
+                Child::Internal { node_index, aabb } => {
+                    if visitor.enter_node(depth, &aabb, false) {
+                        let node = &nodes[node_index as usize];
+                        stack.extend((0..8).rev().map(|slot| (node.child(slot), depth + 1)));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Quality statistics.
+    pub fn stats(&self) -> Stats {
+        let mut builder = StatsBuilder::default();
+        self.walk(&mut builder);
+        let memory = std::mem::size_of_val(self.nodes()) + std::mem::size_of_val(self.primitives());
+        builder.finish(memory)
+    }
+
+    /// Quality statistics, including the expensive [`Stats::epo_cost`].
+    pub fn stats_with_epo(&self) -> Stats {
+        let epo_cost = stats::epo_cost(
+            |builder| self.walk(builder),
+            |prim| super::triangle(&self.positions, prim),
+        );
+        Stats {
+            epo_cost: Some(epo_cost),
+            ..self.stats()
+        }
+    }
+
+    /// Write the node bounds as boxes in the Wavefront OBJ format, for visual debugging.
+    ///
+    /// Only nodes with a depth in `depth_range` are exported, the root being at depth `0`.
+    /// Boxes are grouped in one object per depth, named `depth_<depth>`.
+    ///
+    /// See [`BVH::walk`] for the node bounds.
+    pub fn export_obj<W, R>(&self, writer: W, depth_range: R) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+        R: std::ops::RangeBounds<u32>,
+    {
+        let mut exporter = ObjExporter::new(depth_range);
+        self.walk(&mut exporter);
+        exporter.write(writer)
+    }
+
+    /// Check the BVH structure.
+    ///
+    /// Verifies that nodes are reached once from the root, that children are
+    /// enclosed by their parent, and that leaves contain their triangles, each
+    /// primitive being referenced exactly once.
+    ///
+    /// Leaf errors are reported on the node holding the leaf slot. Bounds are
+    /// compared with one quantization step of tolerance.
+    ///
+    /// Spatial splits (see [`BVH::build_hq`]) are handled as in [`crate::wald::BVH::validate`].
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let nodes = self
+            .try_nodes()
+            .map_err(|err| vec![ValidationError::Layout(err)])?;
+        let primitives = self.primitives();
+        let primitive_count = self.positions.len() / 3;
+        if nodes.is_empty() && primitive_count == 0 {
+            return Ok(());
+        }
+
+        let mut validator = Validator::new(nodes.len(), primitive_count, self.spatial_splits);
+        let mut stack = Vec::new();
+        if validator.visit(0, 0) {
+            stack.push(0);
+        }
+        while let Some(id) = stack.pop() {
+            let node = &nodes[id as usize];
+            let tolerance = node.quantization_step();
+            for child in node.children() {
+                match child {
+                    Child::Empty => {}
+                    Child::Internal { node_index, aabb } => {
+                        if !validator.visit(id, node_index) {
+                            continue;
+                        }
+                        let child = &nodes[node_index as usize];
+                        let bounds =
+                            child
+                                .children()
+                                .fold(Aabb::empty(), |bounds, child| match child {
+                                    Child::Empty => bounds,
+                                    Child::Internal { aabb, .. } | Child::Leaf { aabb, .. } => {
+                                        bounds.union(&aabb)
+                                    }
+                                });
+                        let tolerance = tolerance.max(child.quantization_step());
+                        let err = ValidationError::ChildNotEnclosed {
+                            parent: id,
+                            child: node_index,
+                        };
+                        validator.enclosed(&aabb, &bounds, tolerance, err);
+                        stack.push(node_index);
+                    }
+                    Child::Leaf {
+                        primitives: range,
+                        aabb,
+                    } => {
+                        let Some(leaf) = primitives.get(range.start as usize..range.end as usize)
+                        else {
+                            validator
+                                .errors
+                                .push(ValidationError::InvalidPrimitiveRange {
+                                    node: id,
+                                    start: range.start,
+                                    count: range.len() as u32,
+                                });
+                            continue;
+                        };
+                        for primitive in leaf {
+                            let prim = primitive.original_primitive;
+                            validator.triangle(id, &aabb, prim, || primitive.vertices(), tolerance);
+                        }
+                    }
+                }
+            }
+        }
+        validator.finish()
+    }
+
+    /// Rust-side closest hit traversal.
+    fn traverse(&self, ray: &mut Ray, options: &QueryOptions) -> TraversalStats {
+        let nodes = self.nodes();
+        let primitives = self.primitives();
+        if nodes.is_empty() || primitives.is_empty() {
+            return TraversalStats::default();
+        }
+        let children = |id: u32| {
+            let node = &nodes[id as usize];
+            (0..8)
+                .filter(|&slot| node.child_meta[slot] != 0)
+                .map(|slot| {
+                    let child = if node.is_internal(slot) {
+                        WideChild::Internal(node.child_index(slot))
+                    } else {
+                        WideChild::Leaf(node.child_primitives(slot))
+                    };
+                    (node.child_aabb(slot), child)
+                })
+        };
+        traverse_wide(ray, children, |ray, range| {
+            let count = range.len() as u32;
+            for prim in range {
+                let primitive = &primitives[prim as usize];
+                options.intersect_triangle(ray, primitive.vertices(), primitive.original_primitive);
+            }
+            count
+        })
     }
 
     pub fn new_internal() -> Self {
         Self {
             inner: ffi::CWBVH_new(),
-            _phantom: PhantomData,
+            positions: crate::layouts::empty_positions(),
+            spatial_splits: false,
         }
     }
 }
 super::impl_bvh!(BVH, BVH8_CWBVH);
+
+impl crate::InstrumentedIntersector for BVH<'_> {
+    fn intersect_instrumented(&self, ray: &mut Ray) -> TraversalStats {
+        self.traverse(ray, &QueryOptions::default())
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{
+    stats::{self, StatsBuilder},
+    wald, Aabb, Positions, Ray, Stats,
+};
+
+/// Alternative 64-bytes BVH node layout, as proposed by Aila & Laine.
+///
+/// Stores the bounds of both children, allowing to test them without
+/// fetching the child nodes. Traversal is cheaper on the GPU at the
+/// cost of memory.
+///
+/// Node layout used by [`BVH`].
+#[repr(C)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Node {
+    /// Left child AABB min position.
+    pub lmin: [f32; 3],
+    /// Left child node index.
+    pub left: u32,
+    /// Left child AABB max position.
+    pub lmax: [f32; 3],
+    /// Right child node index.
+    pub right: u32,
+    /// Right child AABB min position.
+    pub rmin: [f32; 3],
+    /// If the node is a leaf, number of triangles in the node.
+    /// `0` otherwise.
+    pub tri_count: u32,
+    /// Right child AABB max position.
+    pub rmax: [f32; 3],
+    /// If the node is a leaf, start index of the primitives.
+    pub first_tri: u32,
+}
+
+impl Node {
+    /// Returns `true` if the node is a leaf.
+    pub fn is_leaf(&self) -> bool {
+        self.tri_count > 0
+    }
+
+    /// Left child bounding box.
+    pub fn left_aabb(&self) -> Aabb {
+        Aabb::new(self.lmin, self.lmax)
+    }
+
+    /// Right child bounding box.
+    pub fn right_aabb(&self) -> Aabb {
+        Aabb::new(self.rmin, self.rmax)
+    }
+}
+
+/// BVH with node layout [`Node`].
+///
+/// Converted from a [`wald::BVH`], kept to share its primitive indices.
+///
+/// # Examples
+///
+/// ```
+/// use tinybvh_rs::{gpu, wald, Intersector, Ray};
+///
+/// let triangles = vec![
+///     [-1.0, 1.0, 0.0, 0.0],
+///     [1.0, 1.0, 0.0, 0.0],
+///     [-1.0, 0.0, 0.0, 0.0]
+/// ];
+/// let bvh = gpu::BVH::new(wald::BVH::new(&triangles));
+/// let nodes: &[gpu::Node] = bvh.nodes(); // Upload to the GPU.
+///
+/// let mut ray = Ray::new([-0.5, 0.5, 1.0], [0.0, 0.0, -1.0]);
+/// bvh.intersect(&mut ray);
+/// println!("Hit distance: {}", ray.hit.t); // 1.0
+/// ```
+pub struct BVH<'a> {
+    bvh: wald::BVH<'a>,
+    nodes: Vec<Node>,
+}
+
+impl<'a> BVH<'a> {
+    /// Convert a [`wald::BVH`].
+    ///
+    /// Nodes are stored depth first, with the root at index `0`.
+    pub fn new(bvh: wald::BVH<'a>) -> Self {
+        let mut nodes = Vec::with_capacity(bvh.nodes().len());
+        if !bvh.indices().is_empty() {
+            convert(bvh.nodes(), 0, &mut nodes);
+        }
+        Self { bvh, nodes }
+    }
+
+    /// Source BVH.
+    pub fn bvh(&self) -> &wald::BVH<'a> {
+        &self.bvh
+    }
+
+    /// Positions used to build the BVH.
+    pub fn positions(&self) -> &Positions<'a> {
+        self.bvh.positions()
+    }
+
+    /// BVH nodes.
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// BVH indices.
+    ///
+    /// Map from leaf entry to primitive index.
+    pub fn indices(&self) -> &[u32] {
+        self.bvh.indices()
+    }
+
+    /// Quality statistics.
+    ///
+    /// The hierarchy is the same as the source BVH, only [`Stats::memory`] differs.
+    pub fn stats(&self) -> Stats {
+        let mut builder = StatsBuilder::default();
+        self.bvh.walk(&mut builder);
+        let memory = std::mem::size_of_val(self.nodes()) + std::mem::size_of_val(self.indices());
+        builder.finish(memory)
+    }
+
+    /// Quality statistics, including the expensive [`Stats::epo_cost`].
+    pub fn stats_with_epo(&self) -> Stats {
+        let epo_cost = stats::epo_cost(
+            |builder| self.bvh.walk(builder),
+            |prim| super::triangle(self.positions(), prim),
+        );
+        Stats {
+            epo_cost: Some(epo_cost),
+            ..self.stats()
+        }
+    }
+}
+
+impl<'a> From<wald::BVH<'a>> for BVH<'a> {
+    fn from(bvh: wald::BVH<'a>) -> Self {
+        Self::new(bvh)
+    }
+}
+
+impl crate::Intersector for BVH<'_> {
+    fn intersect(&self, ray: &mut Ray) -> u32 {
+        if self.nodes.is_empty() {
+            return 0;
+        }
+        let children = |id: u32| {
+            let node = &self.nodes[id as usize];
+            (!node.is_leaf()).then(|| {
+                [
+                    (node.left, node.left_aabb()),
+                    (node.right, node.right_aabb()),
+                ]
+            })
+        };
+        let options = crate::QueryOptions::default();
+        let stats = wald::traverse_binary(ray, None, children, |ray, id| {
+            let node = &self.nodes[id as usize];
+            let start = node.first_tri as usize;
+            for &prim in &self.indices()[start..start + node.tri_count as usize] {
+                let triangle = super::triangle(self.positions(), prim);
+                options.intersect_triangle(ray, triangle, prim);
+            }
+            node.tri_count
+        });
+        stats.node_visits
+    }
+}
+
+/// Convert the subtree rooted at wald node `id`, returning its [`Node`] index.
+fn convert(wald_nodes: &[wald::Node], id: u32, nodes: &mut Vec<Node>) -> u32 {
+    let index = nodes.len() as u32;
+    nodes.push(Node::default());
+    let node = &wald_nodes[id as usize];
+    if node.is_leaf() {
+        nodes[index as usize].tri_count = node.tri_count;
+        nodes[index as usize].first_tri = node.left_first;
+        return index;
+    }
+
+    let (left, right) = (node.left_first, node.left_first + 1);
+    let left_index = convert(wald_nodes, left, nodes);
+    let right_index = convert(wald_nodes, right, nodes);
+    let (left, right) = (&wald_nodes[left as usize], &wald_nodes[right as usize]);
+    nodes[index as usize] = Node {
+        lmin: left.min,
+        left: left_index,
+        lmax: left.max,
+        right: right_index,
+        rmin: right.min,
+        tri_count: 0,
+        rmax: right.max,
+        first_tri: 0,
+    };
+    index
+}
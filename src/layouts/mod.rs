@@ -1,4 +1,12 @@
+pub mod bvh4_gpu;
+pub mod curves;
+pub mod custom;
 pub mod cwbvh;
+pub mod gpu;
+pub mod motion;
+pub mod quads;
+pub mod spheres;
+pub mod tlas;
 pub mod wald;
 
 /// Holds BVH data without lifetfime bound.
@@ -8,6 +16,33 @@ pub struct Capture<T> {
     inner: T,
 }
 
+/// Positions used by a BVH that has not been built yet.
+pub(crate) fn empty_positions<'a>() -> crate::Positions<'a> {
+    const EMPTY: &[[f32; 4]] = &[];
+    EMPTY.into()
+}
+
+/// Vertices of triangle `prim`, for positions holding 3 vertices per primitive.
+pub(crate) fn triangle(positions: &crate::Positions, prim: u32) -> [[f32; 3]; 3] {
+    let start = prim as usize * 3;
+    let vertex = |i: usize| {
+        let p = positions[start + i];
+        [p[0], p[1], p[2]]
+    };
+    [vertex(0), vertex(1), vertex(2)]
+}
+
+/// Result of a query, with the hit side read from the original triangle.
+pub(crate) fn query_result(
+    positions: &crate::Positions,
+    ray: &crate::Ray,
+    steps: u32,
+) -> crate::QueryResult {
+    let front_face = ray.hit.t < crate::INFINITE
+        && crate::traversal::front_face(ray, triangle(positions, ray.hit.prim));
+    crate::QueryResult { steps, front_face }
+}
+
 /// Implement shared BVH layout.
 ///
 /// - Temporarily move the BVH to edit the triangles
@@ -43,7 +78,8 @@ macro_rules! impl_bvh {
             ) -> Self {
                 Self {
                     inner: capture.inner,
-                    _phantom: PhantomData,
+                    positions: crate::layouts::empty_positions(),
+                    spatial_splits: false,
                 }
                 .build(primitives)
             }
@@ -56,10 +92,11 @@ macro_rules! impl_bvh {
                 if slice.len() % 3 != 0 {
                     panic!("primitives slice must triangulated (size multiple of 3)")
                 }
-                self.inner.pin_mut().Build(&slice.into());
+                self.inner.pin_mut().Build(&(&slice).into());
                 Self {
                     inner: self.inner,
-                    _phantom: PhantomData,
+                    positions: slice,
+                    spatial_splits: false,
                 }
             }
 
@@ -71,11 +108,51 @@ macro_rules! impl_bvh {
                 if slice.len() % 3 != 0 {
                     panic!("primitives slice must triangulated (size multiple of 3)")
                 }
-                self.inner.pin_mut().BuildHQ(&slice.into());
+                self.inner.pin_mut().BuildHQ(&(&slice).into());
                 Self {
                     inner: self.inner,
-                    _phantom: PhantomData,
+                    positions: slice,
+                    spatial_splits: true,
+                }
+            }
+
+            /// Positions used to build the BVH.
+            pub fn positions(&self) -> &crate::Positions<'a> {
+                &self.positions
+            }
+
+            /// Returns `true` if built with [`Self::build_hq`], whose leaves can
+            /// reference primitives several times and clip them (spatial splits).
+            pub fn spatial_splits(&self) -> bool {
+                self.spatial_splits
+            }
+
+            /// Surface data of the ray hit, computed from the build positions.
+            ///
+            /// Returns `None` if the ray did not hit anything.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// use tinybvh_rs::{wald, Intersector, Ray};
+            ///
+            /// let triangles = vec![
+            ///     [-1.0, 1.0, 0.0, 0.0],
+            ///     [1.0, 1.0, 0.0, 0.0],
+            ///     [-1.0, 0.0, 0.0, 0.0]
+            /// ];
+            /// let bvh = wald::BVH::new(&triangles);
+            /// let mut ray = Ray::new([-0.5, 0.5, 1.0], [0.0, 0.0, -1.0]);
+            /// bvh.intersect(&mut ray);
+            /// let info = bvh.hit_info(&ray).unwrap();
+            /// println!("Point & normal: {:?} / {:?}", info.point, info.normal); // [-0.5, 0.5, 0.0] / [0.0, 0.0, -1.0]
+            /// ```
+            pub fn hit_info(&self, ray: &crate::Ray) -> Option<crate::HitInfo> {
+                if ray.hit.t >= crate::INFINITE {
+                    return None;
                 }
+                let triangle = crate::layouts::triangle(&self.positions, ray.hit.prim);
+                Some(crate::HitInfo::new(triangle, &ray.hit))
             }
 
             /// Temporarily move the BVH to loosen the primitives lifetime.
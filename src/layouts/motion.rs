@@ -0,0 +1,195 @@
+use crate::{
+    math,
+    stats::{self, StatsBuilder},
+    wald, Aabb, InstrumentedIntersector, Positions, QueryOptions, Ray, Stats, TraversalStats,
+};
+
+/// BVH over keyframed positions, used for deformation motion blur.
+///
+/// The hierarchy is built over the first keyframe using [`wald::BVH`],
+/// and refitted for every keyframe. During intersection, vertices and
+/// node bounds are linearly interpolated at [`Ray::time`].
+///
+/// # Examples
+///
+/// ```
+/// use tinybvh_rs::{motion, Intersector, Ray};
+///
+/// let start = vec![
+///     [-1.0, 1.0, 0.0, 0.0],
+///     [1.0, 1.0, 0.0, 0.0],
+///     [-1.0, 0.0, 0.0, 0.0]
+/// ];
+/// let end = vec![
+///     [-1.0, 1.0, -1.0, 0.0],
+///     [1.0, 1.0, -1.0, 0.0],
+///     [-1.0, 0.0, -1.0, 0.0]
+/// ];
+/// let bvh = motion::BVH::new([&start, &end]);
+///
+/// let mut ray = Ray::with_time([-0.5, 0.5, 1.0], [0.0, 0.0, -1.0], 0.5);
+/// bvh.intersect(&mut ray);
+/// println!("Hit distance: {}", ray.hit.t); // 1.5
+/// ```
+pub struct BVH<'a> {
+    bvh: wald::BVH<'a>,
+    /// Keyframes, excluding the first one held by `bvh`.
+    keys: Vec<Positions<'a>>,
+    /// Node bounds, one entry per keyframe for each node.
+    bounds: Vec<Aabb>,
+}
+
+impl<'a> BVH<'a> {
+    /// Create a new BVH from keyframes.
+    ///
+    /// # Notes
+    ///
+    /// Each keyframe must contain 3 positions per primitive, and all
+    /// keyframes must have the same length.
+    pub fn new<S, I>(keys: I) -> Self
+    where
+        S: Into<Positions<'a>>,
+        I: IntoIterator<Item = S>,
+    {
+        let mut keys = keys.into_iter().map(Into::into);
+        let first = keys.next().expect("at least one keyframe is required");
+        let bvh = wald::BVH::new(first);
+        let keys: Vec<Positions<'a>> = keys.collect();
+        if keys.iter().any(|k| k.len() != bvh.positions().len()) {
+            panic!("keyframes must have the same length")
+        }
+
+        let mut result = Self {
+            bounds: vec![Aabb::default(); bvh.nodes().len() * (keys.len() + 1)],
+            bvh,
+            keys,
+        };
+        if !result.bvh.indices().is_empty() {
+            for key in 0..result.keys_count() {
+                result.refit(0, key);
+            }
+        }
+        result
+    }
+
+    /// Number of keyframes.
+    pub fn keys_count(&self) -> usize {
+        self.keys.len() + 1
+    }
+
+    /// Hierarchy built over the first keyframe.
+    pub fn bvh(&self) -> &wald::BVH<'a> {
+        &self.bvh
+    }
+
+    /// Quality statistics, computed over the first keyframe.
+    ///
+    /// [`Stats::memory`] includes the refitted bounds of every keyframe.
+    pub fn stats(&self) -> Stats {
+        let mut builder = StatsBuilder::default();
+        self.bvh.walk(&mut builder);
+        let memory = std::mem::size_of_val(self.bvh.nodes())
+            + std::mem::size_of_val(self.bvh.indices())
+            + std::mem::size_of_val(&self.bounds[..]);
+        builder.finish(memory)
+    }
+
+    /// Quality statistics, including the expensive [`Stats::epo_cost`].
+    ///
+    /// Computed over the first keyframe.
+    pub fn stats_with_epo(&self) -> Stats {
+        let epo_cost = stats::epo_cost(
+            |builder| self.bvh.walk(builder),
+            |prim| self.triangle_at(prim, 0.0),
+        );
+        Stats {
+            epo_cost: Some(epo_cost),
+            ..self.stats()
+        }
+    }
+
+    /// Bounds of node `id` at keyframe `key`.
+    pub fn node_bounds(&self, id: u32, key: usize) -> Aabb {
+        self.bounds[id as usize * self.keys_count() + key]
+    }
+
+    /// Bounds of node `id`, interpolated at `time`.
+    pub fn node_bounds_at(&self, id: u32, time: f32) -> Aabb {
+        let (key, alpha) = self.keyframe(time);
+        let next = (key + 1).min(self.keys_count() - 1);
+        let start = self.node_bounds(id, key);
+        let end = self.node_bounds(id, next);
+        Aabb::new(
+            math::lerp(start.min, end.min, alpha),
+            math::lerp(start.max, end.max, alpha),
+        )
+    }
+
+    /// Vertices of primitive `prim`, interpolated at `time`.
+    pub fn triangle_at(&self, prim: u32, time: f32) -> [[f32; 3]; 3] {
+        let (key, alpha) = self.keyframe(time);
+        let next = (key + 1).min(self.keys_count() - 1);
+        let start = super::triangle(self.positions(key), prim);
+        let end = super::triangle(self.positions(next), prim);
+        [0, 1, 2].map(|i| math::lerp(start[i], end[i], alpha))
+    }
+
+    fn positions(&self, key: usize) -> &Positions<'a> {
+        match key {
+            0 => self.bvh.positions(),
+            _ => &self.keys[key - 1],
+        }
+    }
+
+    /// Keyframe preceding `time`, and interpolation weight to the next one.
+    fn keyframe(&self, time: f32) -> (usize, f32) {
+        let segments = (self.keys_count() - 1) as f32;
+        let t = time.clamp(0.0, 1.0) * segments;
+        let key = (t.floor() as usize).min(self.keys_count() - 1);
+        (key, t - key as f32)
+    }
+
+    fn refit(&mut self, id: u32, key: usize) -> Aabb {
+        let node = self.bvh.nodes()[id as usize];
+        let aabb = if node.is_leaf() {
+            let start = node.left_first as usize;
+            let mut aabb = Aabb::empty();
+            for i in start..start + node.tri_count as usize {
+                let prim = self.bvh.indices()[i];
+                for vertex in super::triangle(self.positions(key), prim) {
+                    aabb.grow(vertex);
+                }
+            }
+            aabb
+        } else {
+            let left = self.refit(node.left_first, key);
+            let right = self.refit(node.left_first + 1, key);
+            left.union(&right)
+        };
+        let index = id as usize * self.keys_count() + key;
+        self.bounds[index] = aabb;
+        aabb
+    }
+}
+
+impl crate::Intersector for BVH<'_> {
+    fn intersect(&self, ray: &mut Ray) -> u32 {
+        self.intersect_instrumented(ray).node_visits
+    }
+}
+
+impl InstrumentedIntersector for BVH<'_> {
+    fn intersect_instrumented(&self, ray: &mut Ray) -> TraversalStats {
+        let time = ray.time();
+        wald::traverse_with(
+            self.bvh.nodes(),
+            self.bvh.indices(),
+            ray,
+            |id| self.node_bounds_at(id as u32, time),
+            |ray, prim| {
+                let triangle = self.triangle_at(prim, time);
+                QueryOptions::default().intersect_triangle(ray, triangle, prim);
+            },
+        )
+    }
+}
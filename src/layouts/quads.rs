@@ -0,0 +1,65 @@
+use super::custom::{self, Primitive};
+use crate::{math, Aabb, Intersection, Positions, Ray};
+
+/// Quad primitive of a [`BVH`], without triangulation.
+///
+/// Each quad is made of 4 consecutive positions, in winding order. Quads are
+/// intersected as two triangles `(v0, v1, v2)` and `(v0, v2, v3)`.
+///
+/// [`Intersection::prim`] is the quad index, and [`Intersection::u`]
+/// and [`Intersection::v`] are the quad space coordinates: `v0` is at
+/// `(0, 0)`, `v1` at `(1, 0)`, `v2` at `(1, 1)`, and `v3` at `(0, 1)`.
+pub struct Quad;
+
+impl Primitive for Quad {
+    const POSITIONS: usize = 4;
+
+    fn aabb(positions: &Positions, prim: u32) -> Aabb {
+        let mut aabb = Aabb::empty();
+        quad(positions, prim).into_iter().for_each(|v| aabb.grow(v));
+        aabb
+    }
+
+    fn intersect(positions: &Positions, ray: &mut Ray, prim: u32) {
+        let [v0, v1, v2, v3] = quad(positions, prim);
+        let first = math::intersect_triangle(ray.origin, ray.dir, [v0, v1, v2])
+            .map(|(t, u, v)| (t, u + v, v));
+        let second = math::intersect_triangle(ray.origin, ray.dir, [v0, v2, v3])
+            .map(|(t, u, v)| (t, u, u + v));
+        for (t, u, v) in first.into_iter().chain(second) {
+            if t > ray.t_min() && t < ray.hit.t {
+                ray.hit = Intersection { t, u, v, prim };
+            }
+        }
+    }
+}
+
+/// BVH over quads, see [`Quad`].
+///
+/// # Examples
+///
+/// ```
+/// use tinybvh_rs::{quads, Intersector, Ray};
+///
+/// let quads = vec![
+///     [0.0, 0.0, 0.0, 0.0],
+///     [1.0, 0.0, 0.0, 0.0],
+///     [1.0, 1.0, 0.0, 0.0],
+///     [0.0, 1.0, 0.0, 0.0],
+/// ];
+/// let bvh = quads::BVH::new(&quads);
+///
+/// let mut ray = Ray::new([0.25, 0.75, 1.0], [0.0, 0.0, -1.0]);
+/// bvh.intersect(&mut ray);
+/// println!("Hit uv: {} / {}", ray.hit.u, ray.hit.v); // 0.25 / 0.75
+/// ```
+pub type BVH<'a> = custom::BVH<'a, Quad>;
+
+/// Vertices of quad `prim`.
+fn quad(positions: &Positions, prim: u32) -> [[f32; 3]; 4] {
+    let start = prim as usize * 4;
+    [0, 1, 2, 3].map(|i| {
+        let p = positions[start + i];
+        [p[0], p[1], p[2]]
+    })
+}
@@ -0,0 +1,78 @@
+use super::custom::{self, Primitive};
+use crate::{math, Aabb, Intersection, Positions, Ray};
+
+/// Sphere primitive of a [`BVH`], used for point clouds.
+///
+/// Each sphere is stored as `[x, y, z, radius]`, following the
+/// 4 components [`Positions`] convention.
+///
+/// [`Intersection::prim`] is the point index, and [`Intersection::u`]
+/// and [`Intersection::v`] are the spherical coordinates of the hit,
+/// respectively the azimuth around the y-axis and the polar angle from
+/// the y-axis, normalized to `[0, 1]`.
+pub struct Sphere;
+
+impl Primitive for Sphere {
+    const POSITIONS: usize = 1;
+
+    fn aabb(positions: &Positions, prim: u32) -> Aabb {
+        let [x, y, z, r] = positions[prim as usize];
+        Aabb::new([x - r, y - r, z - r], [x + r, y + r, z + r])
+    }
+
+    fn intersect(positions: &Positions, ray: &mut Ray, prim: u32) {
+        let [x, y, z, radius] = positions[prim as usize];
+        let center = [x, y, z];
+        let Some(t) = intersect_sphere(ray, center, radius) else {
+            return;
+        };
+        if t >= ray.hit.t {
+            return;
+        }
+        let point = math::add(ray.origin, math::scale(ray.dir, t));
+        let n = math::scale(math::sub(point, center), 1.0 / radius);
+        let u = 0.5 + n[2].atan2(n[0]) / (2.0 * std::f32::consts::PI);
+        let v = n[1].clamp(-1.0, 1.0).acos() / std::f32::consts::PI;
+        ray.hit = Intersection { t, u, v, prim };
+    }
+}
+
+/// BVH over spheres, see [`Sphere`].
+///
+/// # Examples
+///
+/// ```
+/// use tinybvh_rs::{spheres, Intersector, Ray};
+///
+/// let points = vec![[0.0, 0.0, 0.0, 0.5], [2.0, 0.0, 0.0, 0.25]];
+/// let bvh = spheres::BVH::new(&points);
+///
+/// let mut ray = Ray::new([2.0, 0.0, 1.0], [0.0, 0.0, -1.0]);
+/// bvh.intersect(&mut ray);
+/// println!("Hit distance & point: {} / {}", ray.hit.t, ray.hit.prim); // 0.75 / 1
+/// ```
+pub type BVH<'a> = custom::BVH<'a, Sphere>;
+
+impl<'a> BVH<'a> {
+    /// Centers and radii used to build the BVH.
+    pub fn centers(&self) -> &Positions<'a> {
+        self.positions()
+    }
+}
+
+/// Closest ray-sphere intersection distance after [`Ray::t_min`].
+fn intersect_sphere(ray: &Ray, center: [f32; 3], radius: f32) -> Option<f32> {
+    let oc = math::sub(ray.origin, center);
+    let a = math::dot(ray.dir, ray.dir);
+    let b = math::dot(oc, ray.dir);
+    let c = math::dot(oc, oc) - radius * radius;
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let sqrt = discriminant.sqrt();
+    // Far root is used when the origin lies inside the sphere.
+    [(-b - sqrt) / a, (-b + sqrt) / a]
+        .into_iter()
+        .find(|t| *t > ray.t_min())
+}
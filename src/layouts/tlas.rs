@@ -0,0 +1,212 @@
+use crate::{
+    math::{self, Decomposed},
+    wald, Aabb, Intersector, Mat4, Ray, Stats,
+};
+
+/// Number of steps used to bound the motion of an [`Instance`].
+const MOTION_STEPS: usize = 16;
+
+/// Instance of a [`wald::BVH`], placed in the scene with a transform.
+///
+/// The transform is interpolated between [`Instance::start`] and
+/// [`Instance::end`] at [`Ray::time`], for camera shutter motion blur.
+/// See [`Instance::transform_at`].
+#[derive(Clone, Copy)]
+pub struct Instance<'b> {
+    /// Bottom level BVH.
+    pub bvh: &'b wald::BVH<'b>,
+    /// Object to world transform at shutter open.
+    pub start: Mat4,
+    /// Object to world transform at shutter close.
+    pub end: Mat4,
+}
+
+impl<'b> Instance<'b> {
+    /// Create a static instance.
+    pub fn new(bvh: &'b wald::BVH<'b>, transform: Mat4) -> Self {
+        Self {
+            bvh,
+            start: transform,
+            end: transform,
+        }
+    }
+
+    /// Create a moving instance.
+    pub fn moving(bvh: &'b wald::BVH<'b>, start: Mat4, end: Mat4) -> Self {
+        Self { bvh, start, end }
+    }
+
+    /// Object to world transform at `time`.
+    ///
+    /// Both transforms are decomposed into translation, rotation and scale.
+    /// Translation and scale are interpolated linearly, and rotation spherically,
+    /// so that rotating instances stay rigid. Shear isn't supported.
+    pub fn transform_at(&self, time: f32) -> Mat4 {
+        if self.start == self.end {
+            return self.start;
+        }
+        let start = Decomposed::new(&self.start);
+        let end = Decomposed::new(&self.end);
+        start.interpolate(&end, time.clamp(0.0, 1.0)).to_mat4()
+    }
+
+    /// World space bounds, swept over the shutter interval.
+    pub fn aabb(&self) -> Aabb {
+        let Some(root) = self.bvh.nodes().first() else {
+            return Aabb::empty();
+        };
+        let start = Decomposed::new(&self.start);
+        let end = Decomposed::new(&self.end);
+        let keys: Vec<Decomposed> = (0..=MOTION_STEPS)
+            .map(|i| start.interpolate(&end, i as f32 / MOTION_STEPS as f32))
+            .collect();
+
+        // Within a step, points move away from the translated key frame by at
+        // most the rotation angle and scale change of the step, times their
+        // distance to the object origin.
+        let radius = (0..8)
+            .map(|corner| {
+                math::length([0, 1, 2].map(|axis| {
+                    if corner & (1 << axis) == 0 {
+                        root.min[axis]
+                    } else {
+                        root.max[axis]
+                    }
+                }))
+            })
+            .fold(0.0, f32::max);
+        let mut padding = 0.0_f32;
+        for key in keys.windows(2) {
+            let max_scale = [0, 1, 2]
+                .map(|axis| key[0].scale[axis].abs().max(key[1].scale[axis].abs()))
+                .into_iter()
+                .fold(0.0, f32::max);
+            let scale_step = [0, 1, 2]
+                .map(|axis| (key[1].scale[axis] - key[0].scale[axis]).abs())
+                .into_iter()
+                .fold(0.0, f32::max);
+            let angle = math::quat_angle(key[0].rotation, key[1].rotation);
+            // Bounds both the deviation from the start of the step, and the
+            // deviation of the end of the step from the translated start.
+            padding = padding.max(2.0 * (angle * max_scale + scale_step) * radius);
+        }
+
+        let mut aabb = Aabb::empty();
+        for key in &keys {
+            let (min, max) = math::transform_aabb(&key.to_mat4(), root.min, root.max);
+            aabb = aabb.union(&Aabb::new(min, max));
+        }
+        Aabb::new(aabb.min.map(|v| v - padding), aabb.max.map(|v| v + padding))
+    }
+}
+
+/// Top level BVH over [`Instance`].
+///
+/// Instances bounds are swept over the shutter interval, moving instances
+/// thus do not require rebuilding the bottom level BVHs.
+///
+/// # Examples
+///
+/// ```
+/// use tinybvh_rs::{tlas, wald, Intersector, Ray, IDENTITY};
+///
+/// let triangles = vec![
+///     [-1.0, 1.0, 0.0, 0.0],
+///     [1.0, 1.0, 0.0, 0.0],
+///     [-1.0, 0.0, 0.0, 0.0]
+/// ];
+/// let blas = wald::BVH::new(&triangles);
+///
+/// let mut end = IDENTITY;
+/// end[3][2] = -1.0;
+/// let tlas = tlas::BVH::new(vec![tlas::Instance::moving(&blas, IDENTITY, end)]);
+///
+/// let mut ray = Ray::with_time([-0.5, 0.5, 1.0], [0.0, 0.0, -1.0], 0.5);
+/// let instance = tlas.intersect_instance(&mut ray);
+/// println!("Hit distance & instance: {} / {:?}", ray.hit.t, instance); // 1.5 / Some(0)
+/// ```
+pub struct BVH<'b> {
+    instances: Vec<Instance<'b>>,
+    nodes: Vec<wald::Node>,
+    indices: Vec<u32>,
+}
+
+impl<'b> BVH<'b> {
+    /// Create a new top level BVH.
+    pub fn new(instances: Vec<Instance<'b>>) -> Self {
+        let aabbs: Vec<Aabb> = instances.iter().map(Instance::aabb).collect();
+        let (nodes, indices) = wald::build_nodes(&aabbs, 1);
+        Self {
+            instances,
+            nodes,
+            indices,
+        }
+    }
+
+    /// Instances, in the order used for building.
+    pub fn instances(&self) -> &[Instance<'b>] {
+        &self.instances
+    }
+
+    /// BVH nodes.
+    ///
+    /// Leaves reference instances through [`BVH::indices`].
+    pub fn nodes(&self) -> &[wald::Node] {
+        &self.nodes
+    }
+
+    /// Map from leaf entry to instance index.
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Quality statistics of the top level hierarchy.
+    ///
+    /// Leaves contain instances, and instance BVHs aren't included.
+    pub fn stats(&self) -> Stats {
+        wald::stats(&self.nodes, &self.indices)
+    }
+
+    /// Intersect the instances with a ray.
+    ///
+    /// [`Ray::hit`] is mutated with the intersection data, expressed in the
+    /// hit instance space: [`crate::Intersection::prim`] is the primitive
+    /// index in the instance BVH.
+    ///
+    /// Returns the index of the hit instance, if any.
+    ///
+    /// Use [`Intersector::intersect`] to retrieve the number of steps instead.
+    pub fn intersect_instance(&self, ray: &mut Ray) -> Option<u32> {
+        self.trace(ray).1
+    }
+
+    fn trace(&self, ray: &mut Ray) -> (u32, Option<u32>) {
+        let mut hit_instance = None;
+        let mut blas_steps = 0;
+        let time = ray.time();
+        let steps = wald::traverse(&self.nodes, &self.indices, ray, |ray, index| {
+            let instance = &self.instances[index as usize];
+            let world_to_object = math::inverse_affine(&instance.transform_at(time));
+            // Direction isn't normalized, distances are thus preserved.
+            let mut object_ray = Ray::with_time(
+                math::transform_point(&world_to_object, ray.origin),
+                math::transform_vector(&world_to_object, ray.dir),
+                time,
+            );
+            object_ray.set_t_min(ray.t_min());
+            object_ray.hit = ray.hit;
+            blas_steps += instance.bvh.intersect(&mut object_ray);
+            if object_ray.hit.t < ray.hit.t {
+                ray.hit = object_ray.hit;
+                hit_instance = Some(index);
+            }
+        });
+        (steps + blas_steps, hit_instance)
+    }
+}
+
+impl Intersector for BVH<'_> {
+    fn intersect(&self, ray: &mut Ray) -> u32 {
+        self.trace(ray).0
+    }
+}
@@ -1,5 +1,12 @@
-use crate::ffi;
-use std::{fmt::Debug, marker::PhantomData};
+use crate::{
+    export::ObjExporter,
+    ffi, math,
+    stats::{self, StatsBuilder},
+    validation::Validator,
+    Aabb, Mat4, PointHit, QueryOptions, QueryResult, Ray, Sphere, Stats, TraversalStats,
+    ValidationError, Visitor, Volume,
+};
+use std::fmt::Debug;
 
 /// "Traditional" 32-bytes BVH node layout, as proposed by Ingo Wald.
 ///
@@ -24,6 +31,55 @@ impl Node {
     pub fn is_leaf(&self) -> bool {
         self.tri_count > 0
     }
+
+    /// Node bounding box.
+    pub fn aabb(&self) -> Aabb {
+        Aabb::new(self.min, self.max)
+    }
+
+    fn distance_squared(&self, point: [f32; 3]) -> f32 {
+        math::aabb_distance_squared(self.min, self.max, point)
+    }
+}
+
+/// Iterator over the primitives overlapping a [`Volume`].
+///
+/// Created with [`BVH::query_aabb`] and [`BVH::query_sphere`].
+pub struct Overlaps<'b, V> {
+    nodes: &'b [Node],
+    indices: &'b [u32],
+    positions: &'b crate::Positions<'b>,
+    volume: V,
+    stack: Vec<u32>,
+    leaf: std::ops::Range<usize>,
+}
+
+impl<V: Volume> Iterator for Overlaps<'_, V> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(i) = self.leaf.next() {
+                let prim = self.indices[i];
+                let triangle = super::triangle(self.positions, prim);
+                if self.volume.overlaps_triangle(&triangle) {
+                    return Some(prim);
+                }
+                continue;
+            }
+
+            let node = &self.nodes[self.stack.pop()? as usize];
+            if !self.volume.overlaps_aabb(&node.aabb()) {
+                continue;
+            }
+            if node.is_leaf() {
+                let start = node.left_first as usize;
+                self.leaf = start..start + node.tri_count as usize;
+            } else {
+                self.stack.extend([node.left_first + 1, node.left_first]);
+            }
+        }
+    }
 }
 
 /// BVH with node layout [`Node`].
@@ -42,7 +98,9 @@ impl Node {
 /// ```
 pub struct BVH<'a> {
     inner: cxx::UniquePtr<ffi::BVH>,
-    _phantom: PhantomData<&'a [f32; 4]>,
+    positions: crate::Positions<'a>,
+    /// Built with `build_hq`, which can use spatial splits.
+    spatial_splits: bool,
 }
 
 impl<'a> BVH<'a> {
@@ -89,10 +147,383 @@ impl<'a> BVH<'a> {
         ffi::BVH_indices(&self.inner)
     }
 
+    /// Find the closest point on the primitives to `point`.
+    ///
+    /// Primitives further than `max_dist` are ignored.
+    ///
+    /// Returns `None` if no primitive lies within `max_dist`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tinybvh_rs::wald;
+    ///
+    /// let triangles = vec![
+    ///     [-1.0, 1.0, 0.0, 0.0],
+    ///     [1.0, 1.0, 0.0, 0.0],
+    ///     [-1.0, 0.0, 0.0, 0.0]
+    /// ];
+    /// let bvh = wald::BVH::new(&triangles);
+    /// let hit = bvh.closest_point([-0.5, 0.5, 1.0], tinybvh_rs::INFINITE).unwrap();
+    /// println!("Distance: {}", hit.distance); // 1.0
+    /// ```
+    pub fn closest_point(&self, point: [f32; 3], max_dist: f32) -> Option<PointHit> {
+        let nodes = self.nodes();
+        let indices = self.indices();
+        if indices.is_empty() {
+            return None;
+        }
+
+        let mut result = None;
+        let mut best = max_dist * max_dist;
+        let mut stack = vec![0_u32];
+        while let Some(id) = stack.pop() {
+            let node = &nodes[id as usize];
+            if node.distance_squared(point) > best {
+                continue;
+            }
+            if node.is_leaf() {
+                let start = node.left_first as usize;
+                for &prim in &indices[start..start + node.tri_count as usize] {
+                    let [a, b, c] = super::triangle(&self.positions, prim);
+                    let (closest, u, v) = math::closest_point_triangle(point, a, b, c);
+                    let dist = math::length_squared(math::sub(closest, point));
+                    if dist <= best {
+                        best = dist;
+                        result = Some(PointHit {
+                            point: closest,
+                            distance: dist.sqrt(),
+                            u,
+                            v,
+                            prim,
+                        });
+                    }
+                }
+                continue;
+            }
+
+            // Push the furthest child first to visit the closest one first.
+            let (left, right) = (node.left_first, node.left_first + 1);
+            let dist_left = nodes[left as usize].distance_squared(point);
+            let dist_right = nodes[right as usize].distance_squared(point);
+            if dist_left < dist_right {
+                stack.extend([right, left]);
+            } else {
+                stack.extend([left, right]);
+            }
+        }
+        result
+    }
+
+    /// Iterate over the primitives overlapping `aabb`.
+    ///
+    /// Triangles are tested exactly, not only their leaf bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tinybvh_rs::{wald, Aabb};
+    ///
+    /// let triangles = vec![
+    ///     [-1.0, 1.0, 0.0, 0.0],
+    ///     [1.0, 1.0, 0.0, 0.0],
+    ///     [-1.0, 0.0, 0.0, 0.0]
+    /// ];
+    /// let bvh = wald::BVH::new(&triangles);
+    /// let aabb = Aabb::new([-0.5, 0.0, -0.5], [0.5, 1.0, 0.5]);
+    /// let primitives: Vec<u32> = bvh.query_aabb(aabb).collect();
+    /// println!("Primitives: {:?}", primitives); // [0]
+    /// ```
+    pub fn query_aabb(&self, aabb: Aabb) -> Overlaps<'_, Aabb> {
+        self.query(aabb)
+    }
+
+    /// Iterate over the primitives overlapping the sphere at `center`.
+    ///
+    /// Triangles are tested exactly, not only their leaf bounds.
+    pub fn query_sphere(&self, center: [f32; 3], radius: f32) -> Overlaps<'_, Sphere> {
+        self.query(Sphere::new(center, radius))
+    }
+
+    /// Iterate over the primitives overlapping a custom [`Volume`].
+    pub fn query<V: Volume>(&self, volume: V) -> Overlaps<'_, V> {
+        let indices = self.indices();
+        Overlaps {
+            nodes: self.nodes(),
+            indices,
+            positions: &self.positions,
+            volume,
+            stack: if indices.is_empty() { vec![] } else { vec![0] },
+            leaf: 0..0,
+        }
+    }
+
+    /// Gather the primitives visible in a frustum.
+    ///
+    /// Each plane is stored as `[nx, ny, nz, d]`, with the normal pointing
+    /// inside the frustum: a point `p` is inside if `dot(n, p) + d >= 0`.
+    ///
+    /// Subtrees fully inside the frustum are gathered without testing
+    /// their primitives. Primitives are culled conservatively: a triangle
+    /// is rejected only if all its vertices lie outside the same plane.
+    pub fn query_frustum(&self, planes: &[[f32; 4]; 6]) -> Vec<u32> {
+        let nodes = self.nodes();
+        let indices = self.indices();
+        let mut result = Vec::new();
+        if indices.is_empty() {
+            return result;
+        }
+
+        let mut stack = vec![0_u32];
+        while let Some(id) = stack.pop() {
+            let node = &nodes[id as usize];
+            match math::classify_aabb_frustum(node.min, node.max, planes) {
+                math::Containment::Outside => continue,
+                math::Containment::Inside => {
+                    self.gather_primitives(id, &mut result);
+                    continue;
+                }
+                math::Containment::Intersecting => {}
+            }
+            if !node.is_leaf() {
+                stack.extend([node.left_first + 1, node.left_first]);
+                continue;
+            }
+            let start = node.left_first as usize;
+            for &prim in &indices[start..start + node.tri_count as usize] {
+                let triangle = super::triangle(&self.positions, prim);
+                let outside = planes.iter().any(|plane| {
+                    triangle
+                        .iter()
+                        .all(|&v| math::plane_distance(*plane, v) < 0.0)
+                });
+                if !outside {
+                    result.push(prim);
+                }
+            }
+        }
+        result
+    }
+
+    /// Append all primitives referenced by the subtree `id`.
+    fn gather_primitives(&self, id: u32, out: &mut Vec<u32>) {
+        let nodes = self.nodes();
+        let indices = self.indices();
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let node = &nodes[id as usize];
+            if node.is_leaf() {
+                let start = node.left_first as usize;
+                out.extend_from_slice(&indices[start..start + node.tri_count as usize]);
+            } else {
+                stack.extend([node.left_first + 1, node.left_first]);
+            }
+        }
+    }
+
+    /// Intersect this instance with a ray, using custom query options.
+    ///
+    /// [`Ray::hit`] is mutated with the intersection data.
+    ///
+    /// Returns the number of steps performed, and the hit side.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tinybvh_rs::{wald, CullMode, QueryOptions, Ray};
+    ///
+    /// let triangles = vec![
+    ///     [-1.0, 1.0, 0.0, 0.0],
+    ///     [1.0, 1.0, 0.0, 0.0],
+    ///     [-1.0, 0.0, 0.0, 0.0]
+    /// ];
+    /// let bvh = wald::BVH::new(&triangles);
+    /// let mut ray = Ray::new([-0.5, 0.5, 1.0], [0.0, 0.0, -1.0]);
+    /// let result = bvh.intersect_with(&mut ray, &QueryOptions::default());
+    /// println!("Front face: {}", result.front_face); // false
+    ///
+    /// // Back faces are now ignored.
+    /// let options = QueryOptions {
+    ///     cull: CullMode::Back,
+    ///     ..Default::default()
+    /// };
+    /// let mut ray = Ray::new([-0.5, 0.5, 1.0], [0.0, 0.0, -1.0]);
+    /// bvh.intersect_with(&mut ray, &options);
+    /// println!("Hit distance: {}", ray.hit.t); // 1e30
+    /// ```
+    pub fn intersect_with(&self, ray: &mut Ray, options: &QueryOptions) -> QueryResult {
+        let steps = if *options == QueryOptions::default() {
+            crate::intersect_from_t_min(ray, |ray| self.inner.Intersect(ray) as u32)
+        } else {
+            self.traverse(ray, options)
+        };
+        super::query_result(&self.positions, ray, steps)
+    }
+
+    /// Rust-side closest hit traversal.
+    fn traverse(&self, ray: &mut Ray, options: &QueryOptions) -> u32 {
+        traverse(self.nodes(), self.indices(), ray, |ray, prim| {
+            let triangle = super::triangle(&self.positions, prim);
+            options.intersect_triangle(ray, triangle, prim);
+        })
+    }
+
+    /// Walk the BVH depth first, starting from the root.
+    ///
+    /// Left children are visited before right children.
+    pub fn walk<V: Visitor>(&self, visitor: &mut V) {
+        walk(self.nodes(), self.indices(), visitor);
+    }
+
+    /// Quality statistics.
+    pub fn stats(&self) -> Stats {
+        let mut builder = StatsBuilder::default();
+        self.walk(&mut builder);
+        let memory = std::mem::size_of_val(self.nodes()) + std::mem::size_of_val(self.indices());
+        builder.finish(memory)
+    }
+
+    /// Quality statistics, including the expensive [`Stats::epo_cost`].
+    pub fn stats_with_epo(&self) -> Stats {
+        let epo_cost = stats::epo_cost(
+            |builder| self.walk(builder),
+            |prim| super::triangle(&self.positions, prim),
+        );
+        Stats {
+            epo_cost: Some(epo_cost),
+            ..self.stats()
+        }
+    }
+
+    /// Write the node bounds as boxes in the Wavefront OBJ format, for visual debugging.
+    ///
+    /// Only nodes with a depth in `depth_range` are exported, the root being at depth `0`.
+    /// Boxes are grouped in one object per depth, named `depth_<depth>`.
+    ///
+    /// See [`BVH::walk`] for the node bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tinybvh_rs::wald;
+    ///
+    /// let triangles = vec![
+    ///     [-1.0, 1.0, 0.0, 0.0],
+    ///     [1.0, 1.0, 0.0, 0.0],
+    ///     [-1.0, 0.0, 0.0, 0.0]
+    /// ];
+    /// let bvh = wald::BVH::new(&triangles);
+    /// let mut obj = Vec::new();
+    /// bvh.export_obj(&mut obj, 0..4).unwrap();
+    /// assert!(String::from_utf8(obj).unwrap().contains("o depth_0"));
+    /// ```
+    pub fn export_obj<W, R>(&self, writer: W, depth_range: R) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+        R: std::ops::RangeBounds<u32>,
+    {
+        let mut exporter = ObjExporter::new(depth_range);
+        self.walk(&mut exporter);
+        exporter.write(writer)
+    }
+
+    /// Check the BVH structure.
+    ///
+    /// Verifies that nodes are reached once from the root, that children are
+    /// enclosed by their parent, and that leaves contain their triangles, each
+    /// primitive being referenced exactly once.
+    ///
+    /// # Notes
+    ///
+    /// High quality builds (see [`BVH::build_hq`]) can use spatial splits: primitives
+    /// can be referenced by several leaves, and leaf bounds can clip triangles.
+    /// For these builds only, primitives must be referenced at least once, and
+    /// leaves must overlap their triangles.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let nodes = self.nodes();
+        let indices = self.indices();
+        let primitive_count = self.positions.len() / 3;
+        if indices.is_empty() && primitive_count == 0 {
+            return Ok(());
+        }
+
+        let mut validator = Validator::new(nodes.len(), primitive_count, self.spatial_splits);
+        let mut stack = Vec::new();
+        if validator.visit(0, 0) {
+            stack.push(0);
+        }
+        while let Some(id) = stack.pop() {
+            let node = &nodes[id as usize];
+            if !node.is_leaf() {
+                for child in [node.left_first, node.left_first.saturating_add(1)] {
+                    if validator.visit(id, child) {
+                        let err = ValidationError::ChildNotEnclosed { parent: id, child };
+                        validator.enclosed(&node.aabb(), &nodes[child as usize].aabb(), 0.0, err);
+                        stack.push(child);
+                    }
+                }
+                continue;
+            }
+
+            let (start, count) = (node.left_first, node.tri_count);
+            let leaf = (start as usize)
+                .checked_add(count as usize)
+                .and_then(|end| indices.get(start as usize..end));
+            let Some(leaf) = leaf else {
+                let err = ValidationError::InvalidPrimitiveRange {
+                    node: id,
+                    start,
+                    count,
+                };
+                validator.errors.push(err);
+                continue;
+            };
+            for &prim in leaf {
+                let triangle = || super::triangle(&self.positions, prim);
+                validator.triangle(id, &node.aabb(), prim, triangle, 0.0);
+            }
+        }
+        validator.finish()
+    }
+
+    /// Export the BVH into a single buffer, ready to upload to the GPU.
+    ///
+    /// See [`GpuBuffer`] for the layout, and [`WGSL_TRAVERSAL`] / [`GLSL_TRAVERSAL`]
+    /// for the matching traversal shaders.
+    pub fn gpu_buffer(&self) -> GpuBuffer {
+        let nodes = self.nodes();
+        let indices = self.indices();
+        let header = GpuHeader {
+            nodes_offset: 1,
+            nodes_count: nodes.len() as u32,
+            triangles_offset: 1 + nodes.len() as u32 * 2,
+            triangles_count: indices.len() as u32,
+        };
+
+        let mut data: Vec<[u32; 4]> =
+            Vec::with_capacity(header.triangles_offset as usize + indices.len() * 3);
+        data.push(bytemuck::cast(header));
+        data.extend_from_slice(bytemuck::cast_slice(nodes));
+        for &prim in indices {
+            let [v0, v1, v2] = super::triangle(&self.positions, prim);
+            let triangle = GpuTriangle {
+                vertex_0: v0,
+                prim,
+                vertex_1: v1,
+                padding_0: 0,
+                vertex_2: v2,
+                padding_1: 0,
+            };
+            data.extend_from_slice(bytemuck::cast_slice(&[triangle]));
+        }
+        GpuBuffer { data }
+    }
+
     pub fn new_internal() -> Self {
         Self {
             inner: ffi::BVH_new(),
-            _phantom: PhantomData,
+            positions: crate::layouts::empty_positions(),
+            spatial_splits: false,
         }
     }
 }
@@ -100,6 +531,447 @@ super::impl_bvh!(BVH, BVH);
 
 impl crate::Intersector for BVH<'_> {
     fn intersect(&self, ray: &mut crate::Ray) -> u32 {
-        self.inner.Intersect(ray) as u32
+        crate::intersect_from_t_min(ray, |ray| self.inner.Intersect(ray) as u32)
+    }
+}
+
+impl crate::InstrumentedIntersector for BVH<'_> {
+    fn intersect_instrumented(&self, ray: &mut Ray) -> TraversalStats {
+        let nodes = self.nodes();
+        let options = QueryOptions::default();
+        traverse_with(
+            nodes,
+            self.indices(),
+            ray,
+            |id| nodes[id].aabb(),
+            |ray, prim| {
+                let triangle = super::triangle(&self.positions, prim);
+                options.intersect_triangle(ray, triangle, prim);
+            },
+        )
+    }
+}
+
+/// WGSL traversal of a [`GpuBuffer`].
+///
+/// Expects the buffer to be bound as `bvh_data: array<vec4<u32>>`, and
+/// provides `bvh_intersect(ray: BvhRay) -> BvhHit`.
+pub const WGSL_TRAVERSAL: &str = include_str!("../shaders/wald.wgsl");
+
+/// GLSL traversal of a [`GpuBuffer`].
+///
+/// Expects the buffer to be bound as `uvec4 bvh_data[]`, and
+/// provides `BvhHit bvh_intersect(BvhRay ray)`.
+pub const GLSL_TRAVERSAL: &str = include_str!("../shaders/wald.glsl");
+
+/// Header of a [`GpuBuffer`].
+///
+/// Offsets are expressed in 16 bytes blocks.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GpuHeader {
+    /// Offset of the first node.
+    pub nodes_offset: u32,
+    /// Number of nodes, each spanning 2 blocks.
+    pub nodes_count: u32,
+    /// Offset of the first triangle.
+    pub triangles_offset: u32,
+    /// Number of triangles, each spanning 3 blocks.
+    pub triangles_count: u32,
+}
+
+/// Triangle stored by value in a [`GpuBuffer`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GpuTriangle {
+    pub vertex_0: [f32; 3],
+    /// Original primitive index.
+    pub prim: u32,
+    pub vertex_1: [f32; 3],
+    pub padding_0: u32,
+    pub vertex_2: [f32; 3],
+    pub padding_1: u32,
+}
+
+/// BVH packed in a single buffer for GPU traversal.
+///
+/// The buffer is an array of 16 bytes blocks, made of:
+/// - A [`GpuHeader`]
+/// - The BVH [`Node`] array, unchanged
+/// - A [`GpuTriangle`] array, reordered to follow [`BVH::indices`]: leaves
+///   directly reference triangles with [`Node::left_first`]
+///
+/// Created with [`BVH::gpu_buffer`].
+///
+/// # Examples
+///
+/// ```
+/// use tinybvh_rs::wald;
+///
+/// let triangles = vec![
+///     [-1.0, 1.0, 0.0, 0.0],
+///     [1.0, 1.0, 0.0, 0.0],
+///     [-1.0, 0.0, 0.0, 0.0]
+/// ];
+/// let bvh = wald::BVH::new(&triangles);
+/// let buffer = bvh.gpu_buffer();
+/// let bytes: &[u8] = buffer.as_bytes(); // Upload to the GPU.
+/// let shader = format!(
+///     "@group(0) @binding(0) var<storage, read> bvh_data: array<vec4<u32>>;\n{}",
+///     wald::WGSL_TRAVERSAL
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct GpuBuffer {
+    data: Vec<[u32; 4]>,
+}
+
+impl GpuBuffer {
+    /// Buffer header.
+    pub fn header(&self) -> &GpuHeader {
+        bytemuck::cast_ref(&self.data[0])
+    }
+
+    /// BVH nodes.
+    pub fn nodes(&self) -> &[Node] {
+        let header = self.header();
+        let start = header.nodes_offset as usize;
+        bytemuck::cast_slice(&self.data[start..start + header.nodes_count as usize * 2])
+    }
+
+    /// Triangles, in BVH order.
+    pub fn triangles(&self) -> &[GpuTriangle] {
+        let header = self.header();
+        let start = header.triangles_offset as usize;
+        bytemuck::cast_slice(&self.data[start..start + header.triangles_count as usize * 3])
+    }
+
+    /// Buffer blocks.
+    pub fn data(&self) -> &[[u32; 4]] {
+        &self.data
+    }
+
+    /// Buffer content, ready to upload.
+    pub fn as_bytes(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.data)
+    }
+
+    /// Reference traversal, mirroring [`WGSL_TRAVERSAL`] and [`GLSL_TRAVERSAL`].
+    ///
+    /// Useful to validate the buffer on the CPU. Like the shaders, the traversal
+    /// stack holds 64 entries, and nodes pushed on a full stack are skipped.
+    ///
+    /// Returns the number of steps performed.
+    pub fn intersect(&self, ray: &mut Ray) -> u32 {
+        let nodes = self.nodes();
+        let triangles = self.triangles();
+        if triangles.is_empty() {
+            return 0;
+        }
+        let children = |id: u32| {
+            let node = &nodes[id as usize];
+            let left = node.left_first;
+            (!node.is_leaf()).then(|| {
+                [
+                    (left, nodes[left as usize].aabb()),
+                    (left + 1, nodes[left as usize + 1].aabb()),
+                ]
+            })
+        };
+        let stats = traverse_binary(ray, Some(GPU_STACK_SIZE), children, |ray, id| {
+            let node = &nodes[id as usize];
+            for i in node.left_first..node.left_first + node.tri_count {
+                let triangle = &triangles[i as usize];
+                let vertices = [triangle.vertex_0, triangle.vertex_1, triangle.vertex_2];
+                QueryOptions::default().intersect_triangle(ray, vertices, triangle.prim);
+            }
+            node.tri_count
+        });
+        stats.node_visits
+    }
+}
+
+/// Traversal stack size used by the GPU shaders.
+const GPU_STACK_SIZE: usize = 64;
+
+/// Build a [`Node`] hierarchy over primitive bounds.
+///
+/// Splits nodes at the median of the largest centroid axis, until leaves
+/// contain at most `max_leaf_size` primitives. Follows tinybvh layout:
+/// root at index `0`, unused node at index `1`, and siblings stored
+/// next to each other.
+///
+/// Returns the nodes and the primitive indices.
+pub(crate) fn build_nodes(aabbs: &[Aabb], max_leaf_size: usize) -> (Vec<Node>, Vec<u32>) {
+    let mut indices: Vec<u32> = (0..aabbs.len() as u32).collect();
+    if aabbs.is_empty() {
+        return (Vec::new(), indices);
+    }
+
+    let mut nodes = vec![Node::default(); 2];
+    let mut stack = vec![(0, 0, aabbs.len())];
+    while let Some((id, first, count)) = stack.pop() {
+        let prims = &mut indices[first..first + count];
+        let mut aabb = Aabb::empty();
+        let mut centroids = Aabb::empty();
+        for &prim in prims.iter() {
+            aabb = aabb.union(&aabbs[prim as usize]);
+            centroids.grow(aabbs[prim as usize].center());
+        }
+        nodes[id] = Node {
+            min: aabb.min,
+            max: aabb.max,
+            left_first: first as u32,
+            tri_count: count as u32,
+        };
+        if count <= max_leaf_size {
+            continue;
+        }
+
+        let extent = math::sub(centroids.max, centroids.min);
+        let axis = (0..3).fold(0, |best, i| if extent[i] > extent[best] { i } else { best });
+        let half = count / 2;
+        prims.select_nth_unstable_by(half, |a, b| {
+            let a = aabbs[*a as usize].center()[axis];
+            let b = aabbs[*b as usize].center()[axis];
+            a.total_cmp(&b)
+        });
+
+        let left = nodes.len();
+        nodes.extend([Node::default(); 2]);
+        nodes[id].left_first = left as u32;
+        nodes[id].tri_count = 0;
+        stack.extend([(left, first, half), (left + 1, first + half, count - half)]);
+    }
+    (nodes, indices)
+}
+
+/// Depth first walk of a [`Node`] hierarchy, see [`BVH::walk`].
+pub(crate) fn walk<V: Visitor>(nodes: &[Node], indices: &[u32], visitor: &mut V) {
+    if indices.is_empty() {
+        return;
+    }
+    let mut stack = vec![(0, 0)];
+    while let Some((id, depth)) = stack.pop() {
+        let node = &nodes[id as usize];
+        if !visitor.enter_node(depth, &node.aabb(), node.is_leaf()) {
+            continue;
+        }
+        if node.is_leaf() {
+            let start = node.left_first as usize;
+            visitor.leaf(&indices[start..start + node.tri_count as usize]);
+        } else {
+            stack.push((node.left_first + 1, depth + 1));
+            stack.push((node.left_first, depth + 1));
+        }
+    }
+}
+
+/// Quality statistics of a [`Node`] hierarchy, without [`Stats::epo_cost`].
+///
+/// [`Stats::memory`] includes the nodes and the indices.
+pub(crate) fn stats(nodes: &[Node], indices: &[u32]) -> Stats {
+    let mut builder = StatsBuilder::default();
+    walk(nodes, indices, &mut builder);
+    builder.finish(std::mem::size_of_val(nodes) + std::mem::size_of_val(indices))
+}
+
+/// Closest hit traversal of a [`Node`] hierarchy.
+///
+/// `intersect` is called for every primitive of the visited leaves,
+/// and is responsible for updating [`Ray::hit`].
+///
+/// Returns the number of steps performed.
+pub(crate) fn traverse<F: FnMut(&mut Ray, u32)>(
+    nodes: &[Node],
+    indices: &[u32],
+    ray: &mut Ray,
+    intersect: F,
+) -> u32 {
+    traverse_with(nodes, indices, ray, |id| nodes[id].aabb(), intersect).node_visits
+}
+
+/// Same as [`traverse`], with node bounds provided by `bounds`.
+///
+/// Useful for layouts storing bounds outside of [`Node`], such as
+/// animated bounds.
+///
+/// Returns the detailed traversal counters.
+pub(crate) fn traverse_with<B, F>(
+    nodes: &[Node],
+    indices: &[u32],
+    ray: &mut Ray,
+    bounds: B,
+    mut intersect: F,
+) -> TraversalStats
+where
+    B: Fn(usize) -> Aabb,
+    F: FnMut(&mut Ray, u32),
+{
+    if indices.is_empty() {
+        return TraversalStats::default();
+    }
+    let children = |id: u32| {
+        let node = &nodes[id as usize];
+        let left = node.left_first;
+        (!node.is_leaf()).then(|| {
+            [
+                (left, bounds(left as usize)),
+                (left + 1, bounds(left as usize + 1)),
+            ]
+        })
+    };
+    traverse_binary(ray, None, children, |ray, id| {
+        let node = &nodes[id as usize];
+        let start = node.left_first as usize;
+        for &prim in &indices[start..start + node.tri_count as usize] {
+            intersect(ray, prim);
+        }
+        node.tri_count
+    })
+}
+
+/// Closest hit traversal loop of a binary hierarchy, rooted at node `0`.
+///
+/// Children are visited near to far. `children` returns the children of a
+/// node with their bounds, or `None` for leaves. `leaf` intersects the
+/// primitives of a leaf, and returns the number of primitive tests.
+///
+/// With a `stack_size`, children pushed on a full stack are skipped.
+pub(crate) fn traverse_binary<C, L>(
+    ray: &mut Ray,
+    stack_size: Option<usize>,
+    children: C,
+    mut leaf: L,
+) -> TraversalStats
+where
+    C: Fn(u32) -> Option<[(u32, Aabb); 2]>,
+    L: FnMut(&mut Ray, u32) -> u32,
+{
+    let stack_size = stack_size.unwrap_or(usize::MAX);
+    let mut stats = TraversalStats::default();
+    let mut stack = Vec::with_capacity(stack_size.min(64));
+    let mut id = 0;
+    loop {
+        stats.node_visits += 1;
+        match children(id) {
+            None => {
+                stats.leaf_visits += 1;
+                stats.primitive_tests += leaf(ray, id);
+            }
+            Some([a, b]) => {
+                let entry = |(id, aabb): (u32, Aabb)| {
+                    let dist =
+                        math::intersect_aabb(ray.origin, ray.r_d, ray.hit.t, aabb.min, aabb.max);
+                    (id, dist)
+                };
+                let (mut near, mut far) = (entry(a), entry(b));
+                if near.1 > far.1 {
+                    std::mem::swap(&mut near, &mut far);
+                }
+                if near.1 < crate::INFINITE {
+                    if far.1 < crate::INFINITE && stack.len() < stack_size {
+                        stack.push(far);
+                    }
+                    id = near.0;
+                    continue;
+                }
+            }
+        }
+
+        // Pop until finding a node closer than the current hit.
+        loop {
+            let Some((next, dist)) = stack.pop() else {
+                return stats;
+            };
+            if dist < ray.hit.t {
+                id = next;
+                break;
+            }
+        }
+    }
+}
+
+/// Find pairs of potentially colliding primitives between two BVHs.
+///
+/// `transform` maps `b` into the space of `a`. Pairs are reported as
+/// `(prim_a, prim_b)` when the triangle of `a` overlaps the bounds of the
+/// transformed triangle of `b`: this is a conservative test, meant to be
+/// refined with an exact triangle-triangle test.
+///
+/// When `a` and `b` are the same BVH and `transform` is [`crate::IDENTITY`],
+/// each unordered pair is reported once and primitives aren't paired
+/// with themselves. Adjacent primitives are still reported.
+pub fn overlapping_pairs(a: &BVH, b: &BVH, transform: &Mat4) -> Vec<(u32, u32)> {
+    let mut pairs = Vec::new();
+    if a.indices().is_empty() || b.indices().is_empty() {
+        return pairs;
+    }
+    let self_collision = std::ptr::eq(a, b) && *transform == crate::IDENTITY;
+    let (nodes_a, nodes_b) = (a.nodes(), b.nodes());
+
+    let mut stack = vec![(0_u32, 0_u32)];
+    while let Some((id_a, id_b)) = stack.pop() {
+        let node_a = &nodes_a[id_a as usize];
+        let node_b = &nodes_b[id_b as usize];
+        let (min_b, max_b) = math::transform_aabb(transform, node_b.min, node_b.max);
+        if !node_a.aabb().overlaps(&Aabb::new(min_b, max_b)) {
+            continue;
+        }
+
+        match (node_a.is_leaf(), node_b.is_leaf()) {
+            (true, true) => leaf_pairs(a, node_a, b, node_b, transform, self_collision, &mut pairs),
+            (false, true) => descend_a(&mut stack, node_a, id_b),
+            (true, false) => descend_b(&mut stack, id_a, node_b),
+            (false, false) => {
+                // Descend the largest node first to keep the boxes tight.
+                let area_a = math::aabb_surface_area(node_a.min, node_a.max);
+                let area_b = math::aabb_surface_area(min_b, max_b);
+                if area_a >= area_b {
+                    descend_a(&mut stack, node_a, id_b);
+                } else {
+                    descend_b(&mut stack, id_a, node_b);
+                }
+            }
+        }
+    }
+    pairs
+}
+
+fn descend_a(stack: &mut Vec<(u32, u32)>, node_a: &Node, id_b: u32) {
+    stack.extend([(node_a.left_first + 1, id_b), (node_a.left_first, id_b)]);
+}
+
+fn descend_b(stack: &mut Vec<(u32, u32)>, id_a: u32, node_b: &Node) {
+    stack.extend([(id_a, node_b.left_first + 1), (id_a, node_b.left_first)]);
+}
+
+fn leaf_pairs(
+    a: &BVH,
+    node_a: &Node,
+    b: &BVH,
+    node_b: &Node,
+    transform: &Mat4,
+    self_collision: bool,
+    pairs: &mut Vec<(u32, u32)>,
+) {
+    let start_a = node_a.left_first as usize;
+    let start_b = node_b.left_first as usize;
+    let prims_a = &a.indices()[start_a..start_a + node_a.tri_count as usize];
+    let prims_b = &b.indices()[start_b..start_b + node_b.tri_count as usize];
+    for &prim_b in prims_b {
+        let triangle_b =
+            super::triangle(&b.positions, prim_b).map(|v| math::transform_point(transform, v));
+        let min_b = [0, 1, 2].map(|i| triangle_b.iter().fold(f32::MAX, |m, v| m.min(v[i])));
+        let max_b = [0, 1, 2].map(|i| triangle_b.iter().fold(f32::MIN, |m, v| m.max(v[i])));
+        for &prim_a in prims_a {
+            if self_collision && prim_a >= prim_b {
+                continue;
+            }
+            let triangle_a = super::triangle(&a.positions, prim_a);
+            if math::triangle_aabb_overlap(triangle_a, min_b, max_b) {
+                pairs.push((prim_a, prim_b));
+            }
+        }
     }
 }
@@ -4,15 +4,27 @@
 //!
 //! All constructed BVH have a lifetime bound required by tinybvh, which holds to the primitives slice.
 
+mod aabb;
 mod cxx_ffi;
+mod export;
+mod heatmap;
 mod layouts;
+mod math;
+mod query;
 mod ray;
+mod stats;
 mod traversal;
+mod validation;
 
+pub use aabb::*;
 pub(crate) use cxx_ffi::ffi;
+pub use heatmap::*;
 pub use layouts::*;
+pub use query::*;
 pub use ray::*;
+pub use stats::*;
 pub use traversal::*;
+pub use validation::*;
 
 /// Infinite value used for intersection.
 ///
@@ -27,3 +39,16 @@ pub const INFINITE: f32 = 1e30; // Actual valid ieee range: 3.40282347E+38
 /// tinybvh-rs internally requires positions to be vectors of size **4**
 /// and not **3**. This is a requirement of the underlying tinybvh library.
 pub type Positions<'a> = pas::Slice<'a, [f32; 4]>;
+
+/// Column-major 4x4 affine transform.
+///
+/// Same memory layout as `glam::Mat4` or `nalgebra::Matrix4`.
+pub type Mat4 = [[f32; 4]; 4];
+
+/// Identity transform.
+pub const IDENTITY: Mat4 = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
@@ -0,0 +1,524 @@
+//! Small vector helpers used by the Rust-side queries.
+
+use crate::Mat4;
+
+pub(crate) type Vec3 = [f32; 3];
+
+pub(crate) fn add(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+pub(crate) fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub(crate) fn scale(a: Vec3, s: f32) -> Vec3 {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+pub(crate) fn lerp(a: Vec3, b: Vec3, t: f32) -> Vec3 {
+    [0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+pub(crate) fn dot(a: Vec3, b: Vec3) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub(crate) fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub(crate) fn length(a: Vec3) -> f32 {
+    dot(a, a).sqrt()
+}
+
+pub(crate) fn length_squared(a: Vec3) -> f32 {
+    dot(a, a)
+}
+
+pub(crate) fn normalize(a: Vec3) -> Vec3 {
+    scale(a, 1.0 / length(a))
+}
+
+pub(crate) fn transform_point(m: &Mat4, p: Vec3) -> Vec3 {
+    [0, 1, 2].map(|i| m[0][i] * p[0] + m[1][i] * p[1] + m[2][i] * p[2] + m[3][i])
+}
+
+pub(crate) fn transform_vector(m: &Mat4, v: Vec3) -> Vec3 {
+    [0, 1, 2].map(|i| m[0][i] * v[0] + m[1][i] * v[1] + m[2][i] * v[2])
+}
+
+pub(crate) type Quat = [f32; 4];
+
+/// Affine transform decomposed into translation, rotation and scale.
+///
+/// Shear isn't represented.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Decomposed {
+    pub(crate) translation: Vec3,
+    pub(crate) rotation: Quat,
+    pub(crate) scale: Vec3,
+}
+
+impl Decomposed {
+    pub(crate) fn new(m: &Mat4) -> Self {
+        let axes = [0, 1, 2].map(|c| [m[c][0], m[c][1], m[c][2]]);
+        let mut lengths = axes.map(length);
+        // Mirroring transforms are represented with a negative scale along x.
+        if dot(axes[0], cross(axes[1], axes[2])) < 0.0 {
+            lengths[0] = -lengths[0];
+        }
+        Self {
+            translation: [m[3][0], m[3][1], m[3][2]],
+            rotation: quat_from_axes([0, 1, 2].map(|c| scale(axes[c], 1.0 / lengths[c]))),
+            scale: lengths,
+        }
+    }
+
+    pub(crate) fn to_mat4(self) -> Mat4 {
+        let axes = quat_to_axes(self.rotation);
+        let [x, y, z] = [0, 1, 2].map(|c| scale(axes[c], self.scale[c]));
+        let t = self.translation;
+        [
+            [x[0], x[1], x[2], 0.0],
+            [y[0], y[1], y[2], 0.0],
+            [z[0], z[1], z[2], 0.0],
+            [t[0], t[1], t[2], 1.0],
+        ]
+    }
+
+    /// Interpolate translation and scale linearly, and rotation spherically.
+    pub(crate) fn interpolate(&self, other: &Self, t: f32) -> Self {
+        Self {
+            translation: lerp(self.translation, other.translation, t),
+            rotation: slerp(self.rotation, other.rotation, t),
+            scale: lerp(self.scale, other.scale, t),
+        }
+    }
+}
+
+/// Rotation quaternion `[x, y, z, w]` of an orthonormal basis.
+///
+/// From "Quaternion Calculus and Fast Animation", Shoemake 1987.
+fn quat_from_axes([x, y, z]: [Vec3; 3]) -> Quat {
+    let trace = x[0] + y[1] + z[2];
+    let q = if trace > 0.0 {
+        let s = 2.0 * (trace + 1.0).sqrt();
+        [
+            (y[2] - z[1]) / s,
+            (z[0] - x[2]) / s,
+            (x[1] - y[0]) / s,
+            0.25 * s,
+        ]
+    } else if x[0] > y[1] && x[0] > z[2] {
+        let s = 2.0 * (1.0 + x[0] - y[1] - z[2]).sqrt();
+        [
+            0.25 * s,
+            (y[0] + x[1]) / s,
+            (z[0] + x[2]) / s,
+            (y[2] - z[1]) / s,
+        ]
+    } else if y[1] > z[2] {
+        let s = 2.0 * (1.0 + y[1] - x[0] - z[2]).sqrt();
+        [
+            (y[0] + x[1]) / s,
+            0.25 * s,
+            (z[1] + y[2]) / s,
+            (z[0] - x[2]) / s,
+        ]
+    } else {
+        let s = 2.0 * (1.0 + z[2] - x[0] - y[1]).sqrt();
+        [
+            (z[0] + x[2]) / s,
+            (z[1] + y[2]) / s,
+            0.25 * s,
+            (x[1] - y[0]) / s,
+        ]
+    };
+    let len = q.iter().map(|v| v * v).sum::<f32>().sqrt();
+    q.map(|v| v / len)
+}
+
+/// Images of the x, y and z axes by the rotation `q`.
+fn quat_to_axes([x, y, z, w]: Quat) -> [Vec3; 3] {
+    [
+        [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y + w * z),
+            2.0 * (x * z - w * y),
+        ],
+        [
+            2.0 * (x * y - w * z),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z + w * x),
+        ],
+        [
+            2.0 * (x * z + w * y),
+            2.0 * (y * z - w * x),
+            1.0 - 2.0 * (x * x + y * y),
+        ],
+    ]
+}
+
+fn quat_dot(a: Quat, b: Quat) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2] + a[3] * b[3]
+}
+
+/// Rotation angle from `a` to `b`, in radians.
+pub(crate) fn quat_angle(a: Quat, b: Quat) -> f32 {
+    2.0 * quat_dot(a, b).abs().min(1.0).acos()
+}
+
+/// Spherical interpolation between two rotations, along the shortest path.
+pub(crate) fn slerp(a: Quat, mut b: Quat, t: f32) -> Quat {
+    let mut cos = quat_dot(a, b);
+    if cos < 0.0 {
+        b = b.map(|v| -v);
+        cos = -cos;
+    }
+    let (wa, wb) = if cos > 0.9995 {
+        // Nearly identical rotations, fall back to a normalized lerp.
+        (1.0 - t, t)
+    } else {
+        let angle = cos.acos();
+        let sin = angle.sin();
+        (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+    };
+    let q: Quat = [0, 1, 2, 3].map(|i| a[i] * wa + b[i] * wb);
+    let len = quat_dot(q, q).sqrt();
+    q.map(|v| v / len)
+}
+
+/// Inverse of an affine transform.
+pub(crate) fn inverse_affine(m: &Mat4) -> Mat4 {
+    let x = [m[0][0], m[0][1], m[0][2]];
+    let y = [m[1][0], m[1][1], m[1][2]];
+    let z = [m[2][0], m[2][1], m[2][2]];
+    // Rows of the inverse 3x3 matrix are the cofactors, scaled by the determinant.
+    let r0 = cross(y, z);
+    let r1 = cross(z, x);
+    let r2 = cross(x, y);
+    let inv_det = 1.0 / dot(x, r0);
+    let (r0, r1, r2) = (scale(r0, inv_det), scale(r1, inv_det), scale(r2, inv_det));
+    let t = [m[3][0], m[3][1], m[3][2]];
+    [
+        [r0[0], r1[0], r2[0], 0.0],
+        [r0[1], r1[1], r2[1], 0.0],
+        [r0[2], r1[2], r2[2], 0.0],
+        [-dot(r0, t), -dot(r1, t), -dot(r2, t), 1.0],
+    ]
+}
+
+/// Bounds of the box `[min, max]` once transformed by `m`.
+///
+/// From "Transforming Axis-Aligned Bounding Boxes", Arvo 1990.
+pub(crate) fn transform_aabb(m: &Mat4, min: Vec3, max: Vec3) -> (Vec3, Vec3) {
+    let mut out_min = [m[3][0], m[3][1], m[3][2]];
+    let mut out_max = out_min;
+    for col in 0..3 {
+        for row in 0..3 {
+            let a = m[col][row] * min[col];
+            let b = m[col][row] * max[col];
+            out_min[row] += a.min(b);
+            out_max[row] += a.max(b);
+        }
+    }
+    (out_min, out_max)
+}
+
+pub(crate) fn aabb_surface_area(min: Vec3, max: Vec3) -> f32 {
+    let e = sub(max, min);
+    2.0 * (e[0] * e[1] + e[1] * e[2] + e[2] * e[0])
+}
+
+/// Squared distance from `p` to the box `[min, max]`, `0` if inside.
+pub(crate) fn aabb_distance_squared(min: Vec3, max: Vec3, p: Vec3) -> f32 {
+    let mut d = 0.0;
+    for i in 0..3 {
+        let v = (min[i] - p[i]).max(p[i] - max[i]).max(0.0);
+        d += v * v;
+    }
+    d
+}
+
+/// Surface area of the intersection of two boxes, `0` if disjoint.
+pub(crate) fn aabb_overlap_area(a_min: Vec3, a_max: Vec3, b_min: Vec3, b_max: Vec3) -> f32 {
+    let min = [0, 1, 2].map(|i| a_min[i].max(b_min[i]));
+    let max = [0, 1, 2].map(|i| a_max[i].min(b_max[i]));
+    if (0..3).any(|i| min[i] > max[i]) {
+        return 0.0;
+    }
+    aabb_surface_area(min, max)
+}
+
+/// Area of a planar polygon.
+pub(crate) fn polygon_area(vertices: &[Vec3]) -> f32 {
+    let Some(&first) = vertices.first() else {
+        return 0.0;
+    };
+    let mut normal = [0.0; 3];
+    for pair in vertices[1..].windows(2) {
+        normal = add(normal, cross(sub(pair[0], first), sub(pair[1], first)));
+    }
+    0.5 * length(normal)
+}
+
+/// Area of the part of a triangle inside the box `[min, max]`.
+///
+/// Clips the triangle against the box planes (Sutherland-Hodgman).
+pub(crate) fn clipped_triangle_area(tri: [Vec3; 3], min: Vec3, max: Vec3) -> f32 {
+    let mut polygon = tri.to_vec();
+    let mut clipped = Vec::with_capacity(9);
+    for axis in 0..3 {
+        for (bound, sign) in [(min[axis], 1.0), (max[axis], -1.0)] {
+            clipped.clear();
+            for i in 0..polygon.len() {
+                let a = polygon[i];
+                let b = polygon[(i + 1) % polygon.len()];
+                let da = (a[axis] - bound) * sign;
+                let db = (b[axis] - bound) * sign;
+                if da >= 0.0 {
+                    clipped.push(a);
+                }
+                if (da >= 0.0) != (db >= 0.0) {
+                    clipped.push(lerp(a, b, da / (da - db)));
+                }
+            }
+            std::mem::swap(&mut polygon, &mut clipped);
+            if polygon.is_empty() {
+                return 0.0;
+            }
+        }
+    }
+    polygon_area(&polygon)
+}
+
+/// Closest point to `p` on triangle `(a, b, c)`.
+///
+/// Returns the point, and the barycentric weights of `b` and `c`.
+///
+/// From "Real-Time Collision Detection", Ericson 2004.
+pub(crate) fn closest_point_triangle(p: Vec3, a: Vec3, b: Vec3, c: Vec3) -> (Vec3, f32, f32) {
+    let ab = sub(b, a);
+    let ac = sub(c, a);
+    let ap = sub(p, a);
+    let d1 = dot(ab, ap);
+    let d2 = dot(ac, ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return (a, 0.0, 0.0);
+    }
+
+    let bp = sub(p, b);
+    let d3 = dot(ab, bp);
+    let d4 = dot(ac, bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return (b, 1.0, 0.0);
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        let v = d1 / (d1 - d3);
+        return (add(a, scale(ab, v)), v, 0.0);
+    }
+
+    let cp = sub(p, c);
+    let d5 = dot(ab, cp);
+    let d6 = dot(ac, cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return (c, 0.0, 1.0);
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        let w = d2 / (d2 - d6);
+        return (add(a, scale(ac, w)), 0.0, w);
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+        let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return (add(b, scale(sub(c, b), w)), 1.0 - w, w);
+    }
+
+    let denom = 1.0 / (va + vb + vc);
+    let v = vb * denom;
+    let w = vc * denom;
+    (add(a, add(scale(ab, v), scale(ac, w))), v, w)
+}
+
+/// Returns `true` if triangle `tri` overlaps the box `[min, max]`.
+///
+/// Separating axis test from "Fast 3D Triangle-Box Overlap Testing", Akenine-Möller 2001.
+pub(crate) fn triangle_aabb_overlap(tri: [Vec3; 3], min: Vec3, max: Vec3) -> bool {
+    let center = scale(add(min, max), 0.5);
+    let half = scale(sub(max, min), 0.5);
+    let v = tri.map(|p| sub(p, center));
+    let separated = |axis: Vec3| {
+        let p = v.map(|p| dot(p, axis));
+        let r = half[0] * axis[0].abs() + half[1] * axis[1].abs() + half[2] * axis[2].abs();
+        p[0].min(p[1]).min(p[2]) > r || p[0].max(p[1]).max(p[2]) < -r
+    };
+
+    const AXES: [Vec3; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    if AXES.into_iter().any(separated) {
+        return false;
+    }
+    let edges = [sub(v[1], v[0]), sub(v[2], v[1]), sub(v[0], v[2])];
+    if separated(cross(edges[0], edges[1])) {
+        return false;
+    }
+    !edges
+        .into_iter()
+        .any(|edge| AXES.into_iter().any(|axis| separated(cross(edge, axis))))
+}
+
+/// Position of a box relative to a convex volume.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Containment {
+    Outside,
+    Intersecting,
+    Inside,
+}
+
+/// Signed distance from `p` to `plane`, scaled by the plane normal length.
+pub(crate) fn plane_distance(plane: [f32; 4], p: Vec3) -> f32 {
+    plane[0] * p[0] + plane[1] * p[1] + plane[2] * p[2] + plane[3]
+}
+
+/// Classify the box `[min, max]` against planes whose normals point inside.
+pub(crate) fn classify_aabb_frustum(min: Vec3, max: Vec3, planes: &[[f32; 4]]) -> Containment {
+    let mut result = Containment::Inside;
+    for &plane in planes {
+        // Box corners the furthest along, and against, the plane normal.
+        let mut positive = min;
+        let mut negative = max;
+        for i in 0..3 {
+            if plane[i] >= 0.0 {
+                positive[i] = max[i];
+                negative[i] = min[i];
+            }
+        }
+        if plane_distance(plane, positive) < 0.0 {
+            return Containment::Outside;
+        }
+        if plane_distance(plane, negative) < 0.0 {
+            result = Containment::Intersecting;
+        }
+    }
+    result
+}
+
+/// Entry distance of a ray into the box `[min, max]`.
+///
+/// Returns [`crate::INFINITE`] if the ray misses the box, or enters it
+/// further than `t_max`.
+pub(crate) fn intersect_aabb(origin: Vec3, r_d: Vec3, t_max: f32, min: Vec3, max: Vec3) -> f32 {
+    let mut t_near = 0.0_f32;
+    let mut t_far = t_max;
+    for i in 0..3 {
+        let t1 = (min[i] - origin[i]) * r_d[i];
+        let t2 = (max[i] - origin[i]) * r_d[i];
+        t_near = t_near.max(t1.min(t2));
+        t_far = t_far.min(t1.max(t2));
+    }
+    if t_far >= t_near {
+        t_near
+    } else {
+        crate::INFINITE
+    }
+}
+
+/// Ray-triangle intersection.
+///
+/// Returns the distance and the barycentric weights of `v1` and `v2`.
+///
+/// From "Fast, Minimum Storage Ray-Triangle Intersection", Möller & Trumbore 1997.
+pub(crate) fn intersect_triangle(
+    origin: Vec3,
+    dir: Vec3,
+    tri: [Vec3; 3],
+) -> Option<(f32, f32, f32)> {
+    let edge_1 = sub(tri[1], tri[0]);
+    let edge_2 = sub(tri[2], tri[0]);
+    let h = cross(dir, edge_2);
+    let det = dot(edge_1, h);
+    if det.abs() < 1e-7 {
+        return None;
+    }
+    let f = 1.0 / det;
+    let s = sub(origin, tri[0]);
+    let u = f * dot(s, h);
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = cross(s, edge_1);
+    let v = f * dot(dir, q);
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    Some((f * dot(edge_2, q), u, v))
+}
+
+/// Watertight ray-triangle intersection.
+///
+/// Returns the distance and the barycentric weights of `v1` and `v2`.
+///
+/// From "Watertight Ray/Triangle Intersection", Woop et al. 2013.
+pub(crate) fn intersect_triangle_watertight(
+    origin: Vec3,
+    dir: Vec3,
+    tri: [Vec3; 3],
+) -> Option<(f32, f32, f32)> {
+    // Permute axes so that the ray direction is mostly along z.
+    let abs = dir.map(f32::abs);
+    let kz = if abs[0] > abs[1] && abs[0] > abs[2] {
+        0
+    } else if abs[1] > abs[2] {
+        1
+    } else {
+        2
+    };
+    let mut kx = (kz + 1) % 3;
+    let mut ky = (kx + 1) % 3;
+    if dir[kz] < 0.0 {
+        std::mem::swap(&mut kx, &mut ky);
+    }
+
+    // Shear and scale the vertices into ray space.
+    let sx = dir[kx] / dir[kz];
+    let sy = dir[ky] / dir[kz];
+    let sz = 1.0 / dir[kz];
+    let [a, b, c] = tri.map(|v| sub(v, origin));
+    let shear = |p: Vec3| (p[kx] - sx * p[kz], p[ky] - sy * p[kz]);
+    let (ax, ay) = shear(a);
+    let (bx, by) = shear(b);
+    let (cx, cy) = shear(c);
+
+    let mut u = cx * by - cy * bx;
+    let mut v = ax * cy - ay * cx;
+    let mut w = bx * ay - by * ax;
+    // Fall back to double precision on edges.
+    if u == 0.0 || v == 0.0 || w == 0.0 {
+        let edge = |px: f32, py: f32, qx: f32, qy: f32| {
+            (px as f64 * qy as f64 - py as f64 * qx as f64) as f32
+        };
+        u = edge(cx, cy, bx, by);
+        v = edge(ax, ay, cx, cy);
+        w = edge(bx, by, ax, ay);
+    }
+    if (u < 0.0 || v < 0.0 || w < 0.0) && (u > 0.0 || v > 0.0 || w > 0.0) {
+        return None;
+    }
+    let det = u + v + w;
+    if det == 0.0 {
+        return None;
+    }
+
+    let t = u * sz * a[kz] + v * sz * b[kz] + w * sz * c[kz];
+    let inv_det = 1.0 / det;
+    Some((t * inv_det, v * inv_det, w * inv_det))
+}
@@ -0,0 +1,65 @@
+use crate::{math, Aabb};
+
+/// Result of a closest point query.
+///
+/// Contains the closest point, its distance to the query point, as well
+/// as barycentric coordinates.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PointHit {
+    /// Closest point on the primitive.
+    pub point: [f32; 3],
+    /// Distance between the query point and [`PointHit::point`].
+    pub distance: f32,
+    /// Barycentric weight along the first edge.
+    pub u: f32,
+    /// Barycentric weight along the second edge.
+    pub v: f32,
+    /// Primitive index.
+    pub prim: u32,
+}
+
+/// Sphere used for overlap queries.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Sphere {
+    /// Sphere center.
+    pub center: [f32; 3],
+    /// Sphere radius.
+    pub radius: f32,
+}
+
+impl Sphere {
+    /// Create a new sphere.
+    pub fn new(center: [f32; 3], radius: f32) -> Self {
+        Self { center, radius }
+    }
+}
+
+/// Volume tested against BVH nodes and primitives during overlap queries.
+pub trait Volume {
+    /// Returns `true` if the volume overlaps the box.
+    fn overlaps_aabb(&self, aabb: &Aabb) -> bool;
+    /// Returns `true` if the volume overlaps the triangle.
+    fn overlaps_triangle(&self, triangle: &[[f32; 3]; 3]) -> bool;
+}
+
+impl Volume for Aabb {
+    fn overlaps_aabb(&self, aabb: &Aabb) -> bool {
+        self.overlaps(aabb)
+    }
+
+    fn overlaps_triangle(&self, triangle: &[[f32; 3]; 3]) -> bool {
+        math::triangle_aabb_overlap(*triangle, self.min, self.max)
+    }
+}
+
+impl Volume for Sphere {
+    fn overlaps_aabb(&self, aabb: &Aabb) -> bool {
+        math::aabb_distance_squared(aabb.min, aabb.max, self.center) <= self.radius * self.radius
+    }
+
+    fn overlaps_triangle(&self, triangle: &[[f32; 3]; 3]) -> bool {
+        let [a, b, c] = *triangle;
+        let (point, _, _) = math::closest_point_triangle(self.center, a, b, c);
+        math::length_squared(math::sub(point, self.center)) <= self.radius * self.radius
+    }
+}
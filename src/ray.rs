@@ -1,6 +1,6 @@
 use core::f32;
 
-use crate::ffi;
+use crate::{ffi, math};
 
 /// Intersection data.
 ///
@@ -31,13 +31,61 @@ impl Intersection {
     }
 }
 
+/// Surface data at an intersection.
+///
+/// Created with `hit_info()`, for instance [`crate::wald::BVH::hit_info`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HitInfo {
+    /// World space hit position.
+    pub point: [f32; 3],
+    /// Normalized geometric normal.
+    ///
+    /// Follows the primitive winding, use [`HitInfo::facing`] to orient it
+    /// against the ray.
+    pub normal: [f32; 3],
+    /// Primitive vertices.
+    pub vertices: [[f32; 3]; 3],
+}
+
+impl HitInfo {
+    /// Create the hit info of `hit` on `triangle`.
+    pub(crate) fn new(triangle: [[f32; 3]; 3], hit: &Intersection) -> Self {
+        let [v0, v1, v2] = triangle;
+        let edge_1 = math::sub(v1, v0);
+        let edge_2 = math::sub(v2, v0);
+        let normal = math::cross(edge_1, edge_2);
+        Self {
+            point: math::add(
+                v0,
+                math::add(math::scale(edge_1, hit.u), math::scale(edge_2, hit.v)),
+            ),
+            normal: math::scale(normal, 1.0 / math::length(normal)),
+            vertices: triangle,
+        }
+    }
+
+    /// Geometric normal, flipped to face `dir`.
+    pub fn facing(&self, dir: [f32; 3]) -> [f32; 3] {
+        if math::dot(self.normal, dir) > 0.0 {
+            math::scale(self.normal, -1.0)
+        } else {
+            self.normal
+        }
+    }
+}
+
 /// Ray data.
 ///
 /// Origin, distance, and [`Intersection`].
 ///
+/// Intersections are searched in `]t_min, hit.t[`, with [`Ray::t_min`] and
+/// [`Intersection::t`] respectively defaulting to `0` and [`crate::INFINITE`].
+///
 /// # Notes
 ///
-/// Padding is unused and required for optimal alignment and performance.
+/// Padding is ignored by tinybvh and required for optimal alignment and performance.
+/// [`Ray::padding_1`] and [`Ray::padding_2`] store the bits of [`Ray::t_min`] and
+/// [`Ray::time`], use the accessors to read and write them.
 #[repr(C, align(16))]
 #[derive(Clone, Copy, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Ray {
@@ -46,10 +94,12 @@ pub struct Ray {
     pub padding_0: u32,
     /// Ray direction
     pub dir: [f32; 3],
+    /// Bits of [`Ray::t_min`].
     pub padding_1: u32,
     /// Ray inverse direction.
     /// Automatically computed when using [`Ray::new`].
     pub r_d: [f32; 3],
+    /// Bits of [`Ray::time`].
     pub padding_2: u32,
     /// Ray intersection data.
     pub hit: Intersection,
@@ -60,6 +110,215 @@ impl Ray {
     ///
     /// Automatically computes [`Ray::r_d`].
     pub fn new(origin: [f32; 3], dir: [f32; 3]) -> Self {
-        ffi::ray_new(&origin, &dir)
+        Self {
+            padding_1: 0,
+            padding_2: 0,
+            ..ffi::ray_new(&origin, &dir)
+        }
+    }
+
+    /// Create a new ray, only intersecting in `]t_min, t_max[`.
+    ///
+    /// Useful to prevent secondary rays from intersecting the surface they
+    /// spawn from. See also [`offset_origin`].
+    pub fn with_range(origin: [f32; 3], dir: [f32; 3], t_min: f32, t_max: f32) -> Self {
+        let mut ray = Self::new(origin, dir);
+        ray.set_t_min(t_min);
+        ray.hit.t = t_max;
+        ray
+    }
+
+    /// Create a new ray at a given time.
+    ///
+    /// See [`Ray::time`].
+    pub fn with_time(origin: [f32; 3], dir: [f32; 3], time: f32) -> Self {
+        let mut ray = Self::new(origin, dir);
+        ray.set_time(time);
+        ray
+    }
+
+    /// Minimum intersection distance.
+    pub fn t_min(&self) -> f32 {
+        f32::from_bits(self.padding_1)
+    }
+
+    /// Set the minimum intersection distance.
+    pub fn set_t_min(&mut self, t_min: f32) {
+        self.padding_1 = t_min.to_bits();
+    }
+
+    /// Ray time, in `[0, 1]`, used by animated layouts such as [`crate::motion::BVH`].
+    pub fn time(&self) -> f32 {
+        f32::from_bits(self.padding_2)
+    }
+
+    /// Set the ray time, see [`Ray::time`].
+    pub fn set_time(&mut self, time: f32) {
+        self.padding_2 = time.to_bits();
+    }
+}
+
+/// Vertex attribute that can be interpolated with [`interpolate`].
+pub trait Attribute: bytemuck::Pod {
+    /// Weighted sum of three attributes.
+    fn weighted_sum(values: [&Self; 3], weights: [f32; 3]) -> Self;
+}
+
+impl Attribute for f32 {
+    fn weighted_sum(values: [&Self; 3], weights: [f32; 3]) -> Self {
+        values[0] * weights[0] + values[1] * weights[1] + values[2] * weights[2]
+    }
+}
+
+impl<const N: usize> Attribute for [f32; N]
+where
+    [f32; N]: bytemuck::Pod,
+{
+    fn weighted_sum(values: [&Self; 3], weights: [f32; 3]) -> Self {
+        std::array::from_fn(|i| {
+            values[0][i] * weights[0] + values[1][i] * weights[1] + values[2][i] * weights[2]
+        })
+    }
+}
+
+/// Interpolate a vertex attribute at an intersection.
+///
+/// Uses the barycentric coordinates of `hit`. Vertices of primitive `hit.prim`
+/// are read from `indices` if provided, otherwise `attributes` must contain
+/// 3 entries per primitive, as during building.
+///
+/// # Examples
+///
+/// ```
+/// use tinybvh_rs::{interpolate, wald, Intersector, Ray};
+///
+/// #[repr(C)]
+/// #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+/// struct Vertex {
+///     position: [f32; 4],
+///     uv: [f32; 2],
+/// }
+///
+/// let vertices = [
+///     Vertex { position: [-1.0, 1.0, 0.0, 0.0], uv: [0.0, 1.0] },
+///     Vertex { position: [1.0, 1.0, 0.0, 0.0], uv: [1.0, 1.0] },
+///     Vertex { position: [-1.0, 0.0, 0.0, 0.0], uv: [0.0, 0.0] },
+/// ];
+/// let bvh = wald::BVH::new(pas::slice_attr!(vertices, [0].position));
+///
+/// let mut ray = Ray::new([-0.5, 0.5, 1.0], [0.0, 0.0, -1.0]);
+/// bvh.intersect(&mut ray);
+/// let uv: [f32; 2] = interpolate(&ray.hit, pas::slice_attr!(vertices, [0].uv), None);
+/// println!("UV: {:?}", uv); // [0.25, 0.5]
+/// ```
+pub fn interpolate<'a, T, S>(hit: &Intersection, attributes: S, indices: Option<&[u32]>) -> T
+where
+    T: Attribute,
+    S: Into<pas::Slice<'a, T>>,
+{
+    let attributes = attributes.into();
+    let vertex = |i: usize| {
+        let index = hit.prim as usize * 3 + i;
+        match indices {
+            Some(indices) => indices[index] as usize,
+            None => index,
+        }
+    };
+    T::weighted_sum(
+        [
+            &attributes[vertex(0)],
+            &attributes[vertex(1)],
+            &attributes[vertex(2)],
+        ],
+        [1.0 - hit.u - hit.v, hit.u, hit.v],
+    )
+}
+
+/// Run a tinybvh intersection, honoring [`Ray::t_min`].
+///
+/// tinybvh has no notion of minimum distance: the origin is moved
+/// forward by `t_min` for the duration of `intersect`.
+pub(crate) fn intersect_from_t_min<F: FnOnce(&mut Ray) -> u32>(ray: &mut Ray, intersect: F) -> u32 {
+    let t_min = ray.t_min();
+    if t_min <= 0.0 {
+        return intersect(ray);
+    }
+    let origin = ray.origin;
+    ray.origin = [0, 1, 2].map(|i| origin[i] + ray.dir[i] * t_min);
+    ray.hit.t -= t_min;
+    let steps = intersect(ray);
+    ray.origin = origin;
+    ray.hit.t += t_min;
+    steps
+}
+
+/// Offset a ray origin along the geometric normal to prevent self-intersection.
+///
+/// `normal` must point towards the side the new ray is spawned on, i.e.,
+/// flipped for transmission rays.
+///
+/// The offset scales with the magnitude of `point`, by moving it a fixed
+/// number of units in the last place.
+///
+/// From "A Fast and Robust Method for Avoiding Self-Intersection", Wächter & Binder 2019.
+pub fn offset_origin(point: [f32; 3], normal: [f32; 3]) -> [f32; 3] {
+    const ORIGIN: f32 = 1.0 / 32.0;
+    const FLOAT_SCALE: f32 = 1.0 / 65536.0;
+    const INT_SCALE: f32 = 256.0;
+    [0, 1, 2].map(|i| {
+        if point[i].abs() < ORIGIN {
+            return point[i] + FLOAT_SCALE * normal[i];
+        }
+        let offset = (INT_SCALE * normal[i]) as i32;
+        let offset = if point[i] < 0.0 { -offset } else { offset };
+        f32::from_bits((point[i].to_bits() as i32 + offset) as u32)
+    })
+}
+
+/// Ray cone, used for texture level of detail and footprint estimation.
+///
+/// The cone grows linearly with the distance along its central [`Cone::ray`].
+/// Trace the central ray with any [`crate::Intersector`], and use
+/// [`Cone::footprint`] to retrieve the cone width at the hit.
+///
+/// From "Texture Level of Detail Strategies for Real-Time Ray Tracing", Akenine-Möller et al. 2019.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Cone {
+    /// Central ray. [`Ray::hit`] holds the first hit once traced.
+    pub ray: Ray,
+    /// Cone width at the ray origin.
+    pub width: f32,
+    /// Cone spread angle, in radians.
+    pub spread_angle: f32,
+}
+
+impl Cone {
+    /// Create a new cone.
+    ///
+    /// `dir` is normalized, so that [`Ray::hit`] distances are world space distances.
+    pub fn new(origin: [f32; 3], dir: [f32; 3], width: f32, spread_angle: f32) -> Self {
+        Self {
+            ray: Ray::new(origin, math::normalize(dir)),
+            width,
+            spread_angle,
+        }
+    }
+
+    /// Cone width at distance `t` from the origin.
+    pub fn width_at(&self, t: f32) -> f32 {
+        self.width + 2.0 * t * (self.spread_angle * 0.5).tan()
+    }
+
+    /// Cone width at the hit.
+    ///
+    /// The hit distance is scaled by the length of [`Ray::dir`], in case it isn't normalized.
+    ///
+    /// Returns `None` if the cone has not hit anything.
+    pub fn footprint(&self) -> Option<f32> {
+        if self.ray.hit.t < crate::INFINITE {
+            Some(self.width_at(self.ray.hit.t * math::length(self.ray.dir)))
+        } else {
+            None
+        }
     }
 }
@@ -0,0 +1,139 @@
+// Traversal of a `tinybvh_rs::wald::GpuBuffer`.
+//
+// Requires the buffer to be declared by the including shader, e.g.:
+//
+//     layout(std430, binding = 0) readonly buffer BvhBuffer { uvec4 bvh_data[]; };
+
+#define BVH_FAR 1e30
+#define BVH_STACK_SIZE 64u
+#define BVH_INVALID 0xffffffffu
+
+struct BvhRay {
+    vec3 origin;
+    float t_min;
+    vec3 dir;
+    float t_max;
+};
+
+struct BvhHit {
+    float t;
+    float u;
+    float v;
+    // `BVH_INVALID` if nothing was hit.
+    uint prim;
+    uint steps;
+};
+
+struct BvhNode {
+    vec3 min;
+    uint left_first;
+    vec3 max;
+    uint tri_count;
+};
+
+float bvh_safe_rcp(float x) {
+    return abs(x) > 1e-12 ? 1.0 / x : BVH_FAR;
+}
+
+BvhNode bvh_node(uint index) {
+    uvec4 block = bvh_data[bvh_data[0].x + index * 2u];
+    uvec4 block_1 = bvh_data[bvh_data[0].x + index * 2u + 1u];
+    return BvhNode(uintBitsToFloat(block.xyz), block.w, uintBitsToFloat(block_1.xyz), block_1.w);
+}
+
+// Returns the entry distance, or `BVH_FAR` on a miss.
+float bvh_intersect_aabb(vec3 origin, vec3 rd, float t_max, BvhNode node) {
+    vec3 t1 = (node.min - origin) * rd;
+    vec3 t2 = (node.max - origin) * rd;
+    float near = max(max(max(min(t1.x, t2.x), min(t1.y, t2.y)), min(t1.z, t2.z)), 0.0);
+    float far = min(min(min(max(t1.x, t2.x), max(t1.y, t2.y)), max(t1.z, t2.z)), t_max);
+    return far >= near ? near : BVH_FAR;
+}
+
+// Möller–Trumbore intersection, updating `hit` if closer.
+void bvh_intersect_triangle(BvhRay ray, uint index, inout BvhHit hit) {
+    uint offset = bvh_data[0].z + index * 3u;
+    uvec4 b0 = bvh_data[offset];
+    vec3 v0 = uintBitsToFloat(b0.xyz);
+    vec3 v1 = uintBitsToFloat(bvh_data[offset + 1u].xyz);
+    vec3 v2 = uintBitsToFloat(bvh_data[offset + 2u].xyz);
+
+    vec3 edge_1 = v1 - v0;
+    vec3 edge_2 = v2 - v0;
+    vec3 h = cross(ray.dir, edge_2);
+    float a = dot(edge_1, h);
+    if (abs(a) < 1e-7) {
+        return;
+    }
+    float f = 1.0 / a;
+    vec3 s = ray.origin - v0;
+    float u = f * dot(s, h);
+    vec3 q = cross(s, edge_1);
+    float v = f * dot(ray.dir, q);
+    if (u < 0.0 || v < 0.0 || u + v > 1.0) {
+        return;
+    }
+    float t = f * dot(edge_2, q);
+    if (t > ray.t_min && t < hit.t) {
+        hit.t = t;
+        hit.u = u;
+        hit.v = v;
+        hit.prim = b0.w;
+    }
+}
+
+// Closest hit traversal.
+BvhHit bvh_intersect(BvhRay ray) {
+    BvhHit hit = BvhHit(ray.t_max, 0.0, 0.0, BVH_INVALID, 0u);
+    if (bvh_data[0].w == 0u) {
+        return hit;
+    }
+    vec3 rd = vec3(bvh_safe_rcp(ray.dir.x), bvh_safe_rcp(ray.dir.y), bvh_safe_rcp(ray.dir.z));
+
+    uvec2 stack[BVH_STACK_SIZE];
+    uint stack_ptr = 0u;
+    uint index = 0u;
+    while (true) {
+        hit.steps += 1u;
+        BvhNode node = bvh_node(index);
+        if (node.tri_count > 0u) {
+            for (uint i = 0u; i < node.tri_count; i++) {
+                bvh_intersect_triangle(ray, node.left_first + i, hit);
+            }
+        } else {
+            uint near = node.left_first;
+            uint far = node.left_first + 1u;
+            float dist_near = bvh_intersect_aabb(ray.origin, rd, hit.t, bvh_node(near));
+            float dist_far = bvh_intersect_aabb(ray.origin, rd, hit.t, bvh_node(far));
+            if (dist_near > dist_far) {
+                uint tmp = near;
+                near = far;
+                far = tmp;
+                float dist_tmp = dist_near;
+                dist_near = dist_far;
+                dist_far = dist_tmp;
+            }
+            if (dist_near < BVH_FAR) {
+                if (dist_far < BVH_FAR && stack_ptr < BVH_STACK_SIZE) {
+                    stack[stack_ptr++] = uvec2(far, floatBitsToUint(dist_far));
+                }
+                index = near;
+                continue;
+            }
+        }
+
+        bool found = false;
+        while (stack_ptr > 0u) {
+            uvec2 entry = stack[--stack_ptr];
+            if (uintBitsToFloat(entry.y) < hit.t) {
+                index = entry.x;
+                found = true;
+                break;
+            }
+        }
+        if (!found) {
+            break;
+        }
+    }
+    return hit;
+}
@@ -0,0 +1,152 @@
+// Traversal of a `tinybvh_rs::wald::GpuBuffer`.
+//
+// Requires the buffer to be declared by the including shader, e.g.:
+//
+//     @group(0) @binding(0) var<storage, read> bvh_data: array<vec4<u32>>;
+
+const BVH_FAR: f32 = 1e30;
+const BVH_STACK_SIZE: u32 = 64u;
+const BVH_INVALID: u32 = 0xffffffffu;
+
+struct BvhRay {
+    origin: vec3<f32>,
+    t_min: f32,
+    dir: vec3<f32>,
+    t_max: f32,
+}
+
+struct BvhHit {
+    t: f32,
+    u: f32,
+    v: f32,
+    // `BVH_INVALID` if nothing was hit.
+    prim: u32,
+    steps: u32,
+}
+
+struct BvhNode {
+    min: vec3<f32>,
+    left_first: u32,
+    max: vec3<f32>,
+    tri_count: u32,
+}
+
+fn bvh_safe_rcp(x: f32) -> f32 {
+    if (abs(x) > 1e-12) {
+        return 1.0 / x;
+    }
+    return BVH_FAR;
+}
+
+fn bvh_node(index: u32) -> BvhNode {
+    let block = bvh_data[bvh_data[0].x + index * 2u];
+    let block_1 = bvh_data[bvh_data[0].x + index * 2u + 1u];
+    return BvhNode(
+        bitcast<vec3<f32>>(block.xyz),
+        block.w,
+        bitcast<vec3<f32>>(block_1.xyz),
+        block_1.w
+    );
+}
+
+// Returns the entry distance, or `BVH_FAR` on a miss.
+fn bvh_intersect_aabb(origin: vec3<f32>, rd: vec3<f32>, t_max: f32, node: BvhNode) -> f32 {
+    let t1 = (node.min - origin) * rd;
+    let t2 = (node.max - origin) * rd;
+    let near = max(max(max(min(t1.x, t2.x), min(t1.y, t2.y)), min(t1.z, t2.z)), 0.0);
+    let far = min(min(min(max(t1.x, t2.x), max(t1.y, t2.y)), max(t1.z, t2.z)), t_max);
+    if (far >= near) {
+        return near;
+    }
+    return BVH_FAR;
+}
+
+// Möller–Trumbore intersection, updating `hit` if closer.
+fn bvh_intersect_triangle(ray: BvhRay, index: u32, hit: ptr<function, BvhHit>) {
+    let offset = bvh_data[0].z + index * 3u;
+    let b0 = bvh_data[offset];
+    let v0 = bitcast<vec3<f32>>(b0.xyz);
+    let v1 = bitcast<vec3<f32>>(bvh_data[offset + 1u].xyz);
+    let v2 = bitcast<vec3<f32>>(bvh_data[offset + 2u].xyz);
+
+    let edge_1 = v1 - v0;
+    let edge_2 = v2 - v0;
+    let h = cross(ray.dir, edge_2);
+    let a = dot(edge_1, h);
+    if (abs(a) < 1e-7) {
+        return;
+    }
+    let f = 1.0 / a;
+    let s = ray.origin - v0;
+    let u = f * dot(s, h);
+    let q = cross(s, edge_1);
+    let v = f * dot(ray.dir, q);
+    if (u < 0.0 || v < 0.0 || u + v > 1.0) {
+        return;
+    }
+    let t = f * dot(edge_2, q);
+    if (t > ray.t_min && t < (*hit).t) {
+        (*hit).t = t;
+        (*hit).u = u;
+        (*hit).v = v;
+        (*hit).prim = b0.w;
+    }
+}
+
+// Closest hit traversal.
+fn bvh_intersect(ray: BvhRay) -> BvhHit {
+    var hit = BvhHit(ray.t_max, 0.0, 0.0, BVH_INVALID, 0u);
+    if (bvh_data[0].w == 0u) {
+        return hit;
+    }
+    let rd = vec3<f32>(bvh_safe_rcp(ray.dir.x), bvh_safe_rcp(ray.dir.y), bvh_safe_rcp(ray.dir.z));
+
+    var stack: array<vec2<u32>, BVH_STACK_SIZE>;
+    var stack_ptr = 0u;
+    var index = 0u;
+    loop {
+        hit.steps += 1u;
+        let node = bvh_node(index);
+        if (node.tri_count > 0u) {
+            for (var i = 0u; i < node.tri_count; i += 1u) {
+                bvh_intersect_triangle(ray, node.left_first + i, &hit);
+            }
+        } else {
+            var near = node.left_first;
+            var far = node.left_first + 1u;
+            var dist_near = bvh_intersect_aabb(ray.origin, rd, hit.t, bvh_node(near));
+            var dist_far = bvh_intersect_aabb(ray.origin, rd, hit.t, bvh_node(far));
+            if (dist_near > dist_far) {
+                let tmp = near;
+                near = far;
+                far = tmp;
+                let dist_tmp = dist_near;
+                dist_near = dist_far;
+                dist_far = dist_tmp;
+            }
+            if (dist_near < BVH_FAR) {
+                if (dist_far < BVH_FAR && stack_ptr < BVH_STACK_SIZE) {
+                    stack[stack_ptr] = vec2<u32>(far, bitcast<u32>(dist_far));
+                    stack_ptr += 1u;
+                }
+                index = near;
+                continue;
+            }
+        }
+
+        var found = false;
+        while (stack_ptr > 0u) {
+            stack_ptr -= 1u;
+            let entry = stack[stack_ptr];
+            if (bitcast<f32>(entry.y) < hit.t) {
+                index = entry.x;
+                found = true;
+                break;
+            }
+        }
+        if (!found) {
+            break;
+        }
+    }
+    return hit;
+}
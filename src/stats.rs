@@ -0,0 +1,222 @@
+use crate::{math, Aabb, Visitor};
+
+/// Cost of a node traversal, used by [`Stats::sah_cost`] and [`Stats::epo_cost`].
+pub const TRAVERSAL_COST: f32 = 1.0;
+
+/// Cost of a primitive intersection, used by [`Stats::sah_cost`] and [`Stats::epo_cost`].
+pub const INTERSECTION_COST: f32 = 1.0;
+
+/// BVH quality statistics, such as [`crate::wald::BVH::stats`].
+///
+/// Leaves are counted as nodes. For wide layouts storing leaves as
+/// child slots (e.g., [`crate::cwbvh`]), each leaf slot counts as a node.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    /// Number of nodes, including leaves.
+    pub node_count: u32,
+    /// Number of leaves.
+    pub leaf_count: u32,
+    /// Number of leaves per depth, with the root at depth `0`.
+    pub depth_histogram: Vec<u32>,
+    /// Number of leaves per primitive count.
+    pub leaf_size_histogram: Vec<u32>,
+    /// Surface area heuristic cost.
+    pub sah_cost: f32,
+    /// End-point overlap cost.
+    ///
+    /// From "On Quality Metrics of Bounding Volume Hierarchies", Aila et al. 2013.
+    ///
+    /// Expensive to compute: `None` unless requested, such as with
+    /// [`crate::wald::BVH::stats_with_epo`].
+    pub epo_cost: Option<f32>,
+    /// Size of the nodes and primitive data, in bytes.
+    ///
+    /// Doesn't include the positions used to build the BVH.
+    pub memory: usize,
+    /// Average overlap between siblings, relative to the parent surface area.
+    pub average_overlap: f32,
+}
+
+impl Stats {
+    /// Maximum leaf depth.
+    pub fn max_depth(&self) -> u32 {
+        self.depth_histogram.len().saturating_sub(1) as u32
+    }
+}
+
+/// Gather [`Stats`] from a [`Visitor`] walk, without [`Stats::epo_cost`].
+///
+/// Statistics are accumulated during the walk, without storing the tree.
+#[derive(Default)]
+pub(crate) struct StatsBuilder {
+    stats: Stats,
+    root_area: f32,
+    /// Internal nodes being walked, one per depth, with their children bounds.
+    ancestors: Vec<(Aabb, Vec<Aabb>)>,
+    /// Area of the entered leaf, relative to the root area.
+    leaf_area: f32,
+    overlap: f32,
+}
+
+impl Visitor for StatsBuilder {
+    fn enter_node(&mut self, depth: u32, aabb: &Aabb, is_leaf: bool) -> bool {
+        self.close(depth as usize);
+        if let Some((_, children)) = self.ancestors.last_mut() {
+            children.push(*aabb);
+        }
+        let area = math::aabb_surface_area(aabb.min, aabb.max);
+        if self.stats.node_count == 0 {
+            self.root_area = area.max(f32::EPSILON);
+        }
+        self.stats.node_count += 1;
+        let relative_area = area / self.root_area;
+        if !is_leaf {
+            self.stats.sah_cost += TRAVERSAL_COST * relative_area;
+            self.ancestors.push((*aabb, Vec::new()));
+            return true;
+        }
+
+        self.stats.leaf_count += 1;
+        self.leaf_area = relative_area;
+        let depth = depth as usize;
+        if self.stats.depth_histogram.len() <= depth {
+            self.stats.depth_histogram.resize(depth + 1, 0);
+        }
+        self.stats.depth_histogram[depth] += 1;
+        true
+    }
+
+    fn leaf(&mut self, primitives: &[u32]) {
+        let count = primitives.len();
+        self.stats.sah_cost += INTERSECTION_COST * count as f32 * self.leaf_area;
+        if self.stats.leaf_size_histogram.len() <= count {
+            self.stats.leaf_size_histogram.resize(count + 1, 0);
+        }
+        self.stats.leaf_size_histogram[count] += 1;
+    }
+}
+
+impl StatsBuilder {
+    /// Compute the statistics.
+    pub(crate) fn finish(mut self, memory: usize) -> Stats {
+        self.close(0);
+        let internal_count = self.stats.node_count - self.stats.leaf_count;
+        if internal_count > 0 {
+            self.stats.average_overlap = self.overlap / internal_count as f32;
+        }
+        Stats {
+            memory,
+            ..self.stats
+        }
+    }
+
+    /// Leave the internal nodes at `depth` and deeper, accumulating their children overlap.
+    fn close(&mut self, depth: usize) {
+        if self.ancestors.len() <= depth {
+            return;
+        }
+        for (aabb, children) in self.ancestors.drain(depth..) {
+            let area = math::aabb_surface_area(aabb.min, aabb.max);
+            if area <= 0.0 {
+                continue;
+            }
+            let mut overlap = 0.0;
+            for (i, a) in children.iter().enumerate() {
+                for b in &children[i + 1..] {
+                    overlap += math::aabb_overlap_area(a.min, a.max, b.min, b.max);
+                }
+            }
+            self.overlap += overlap / area;
+        }
+    }
+}
+
+/// Compute [`Stats::epo_cost`] of the tree visited by `walk`, using the
+/// primitive triangles returned by `triangle`.
+pub(crate) fn epo_cost<W, T>(walk: W, triangle: T) -> f32
+where
+    W: FnOnce(&mut TreeBuilder),
+    T: Fn(u32) -> [[f32; 3]; 3],
+{
+    let mut builder = TreeBuilder::default();
+    walk(&mut builder);
+    builder.epo_cost(&triangle)
+}
+
+struct Entry {
+    aabb: Aabb,
+    children: Vec<usize>,
+    primitives: Vec<u32>,
+}
+
+/// Gather a tree from a [`Visitor`] walk, see [`epo_cost`].
+#[derive(Default)]
+pub(crate) struct TreeBuilder {
+    entries: Vec<Entry>,
+    /// Entries of the nodes being walked, one per depth.
+    ancestors: Vec<usize>,
+}
+
+impl Visitor for TreeBuilder {
+    fn enter_node(&mut self, depth: u32, aabb: &Aabb, _is_leaf: bool) -> bool {
+        let index = self.entries.len();
+        self.ancestors.truncate(depth as usize);
+        if let Some(&parent) = self.ancestors.last() {
+            self.entries[parent].children.push(index);
+        }
+        self.ancestors.push(index);
+        self.entries.push(Entry {
+            aabb: *aabb,
+            children: Vec::new(),
+            primitives: Vec::new(),
+        });
+        true
+    }
+
+    fn leaf(&mut self, primitives: &[u32]) {
+        if let Some(entry) = self.entries.last_mut() {
+            entry.primitives.extend_from_slice(primitives);
+        }
+    }
+}
+
+impl TreeBuilder {
+    /// Area of the triangles outside of each node subtree, clipped by the node bounds.
+    fn epo_cost<T: Fn(u32) -> [[f32; 3]; 3]>(&self, triangle: &T) -> f32 {
+        let total_area: f32 = self
+            .entries
+            .iter()
+            .flat_map(|entry| &entry.primitives)
+            .map(|&prim| math::polygon_area(&triangle(prim)))
+            .sum();
+        if total_area <= 0.0 {
+            return 0.0;
+        }
+
+        let mut cost = 0.0;
+        let mut stack = Vec::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            let node_cost = if entry.children.is_empty() {
+                INTERSECTION_COST
+            } else {
+                TRAVERSAL_COST
+            };
+            // Skipping the node itself excludes its own subtree.
+            let mut area = 0.0;
+            stack.push(0);
+            while let Some(other) = stack.pop() {
+                let other_entry = &self.entries[other];
+                if other == index || !entry.aabb.overlaps(&other_entry.aabb) {
+                    continue;
+                }
+                stack.extend(&other_entry.children);
+                for &prim in &other_entry.primitives {
+                    let (min, max) = (entry.aabb.min, entry.aabb.max);
+                    area += math::clipped_triangle_area(triangle(prim), min, max);
+                }
+            }
+            cost += node_cost * area;
+        }
+        cost / total_area
+    }
+}
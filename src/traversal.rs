@@ -1,4 +1,4 @@
-use crate::Ray;
+use crate::{math, Aabb, Intersection, Ray};
 
 /// Intersector for BVH and nodes intersection.
 pub trait Intersector {
@@ -9,3 +9,214 @@ pub trait Intersector {
     /// Returns the number of steps (A.K.A intersections) performed.
     fn intersect(&self, ray: &mut Ray) -> u32;
 }
+
+/// Detailed counters of a ray traversal.
+///
+/// Returned by [`InstrumentedIntersector::intersect_instrumented`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TraversalStats {
+    /// Number of visited nodes, including leaves.
+    pub node_visits: u32,
+    /// Number of visited leaves.
+    pub leaf_visits: u32,
+    /// Number of ray-primitive tests.
+    pub primitive_tests: u32,
+}
+
+impl TraversalStats {
+    /// Traversal cost, as the sum of node visits and primitive tests.
+    pub fn cost(&self) -> u32 {
+        self.node_visits + self.primitive_tests
+    }
+}
+
+impl std::ops::Add for TraversalStats {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            node_visits: self.node_visits + other.node_visits,
+            leaf_visits: self.leaf_visits + other.leaf_visits,
+            primitive_tests: self.primitive_tests + other.primitive_tests,
+        }
+    }
+}
+
+/// Intersector reporting detailed traversal counters.
+///
+/// # Notes
+///
+/// Intersection is performed on the Rust side, and might be slower
+/// than [`Intersector::intersect`].
+pub trait InstrumentedIntersector {
+    /// Intersect this instance with a ray.
+    ///
+    /// [`Ray::hit`] is mutated with the intersection data.
+    fn intersect_instrumented(&self, ray: &mut Ray) -> TraversalStats;
+}
+
+/// Visitor for depth first BVH walks, such as [`crate::wald::BVH::walk`].
+///
+/// # Examples
+///
+/// ```
+/// use tinybvh_rs::{wald, Aabb, Visitor};
+///
+/// #[derive(Default)]
+/// struct MaxDepth(u32);
+///
+/// impl Visitor for MaxDepth {
+///     fn enter_node(&mut self, depth: u32, _aabb: &Aabb, _is_leaf: bool) -> bool {
+///         self.0 = self.0.max(depth);
+///         true
+///     }
+/// }
+///
+/// let triangles = vec![
+///     [-1.0, 1.0, 0.0, 0.0],
+///     [1.0, 1.0, 0.0, 0.0],
+///     [-1.0, 0.0, 0.0, 0.0]
+/// ];
+/// let bvh = wald::BVH::new(&triangles);
+/// let mut visitor = MaxDepth::default();
+/// bvh.walk(&mut visitor);
+/// println!("Max depth: {}", visitor.0); // 0
+/// ```
+pub trait Visitor {
+    /// Called when entering a node, with the root at depth `0`.
+    ///
+    /// Returns `false` to skip the node children, or the leaf primitives.
+    fn enter_node(&mut self, depth: u32, aabb: &Aabb, is_leaf: bool) -> bool {
+        let _ = (depth, aabb, is_leaf);
+        true
+    }
+
+    /// Called for each entered leaf, with the original primitive indices.
+    fn leaf(&mut self, primitives: &[u32]) {
+        let _ = primitives;
+    }
+}
+
+/// Options for ray queries, such as [`crate::wald::BVH::intersect_with`].
+///
+/// # Notes
+///
+/// Queries using non-default options are performed on the Rust side,
+/// and might be slower than the tinybvh intersection.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueryOptions {
+    /// Use watertight ray-triangle intersection.
+    ///
+    /// Prevents rays from leaking through edges shared by adjacent triangles.
+    ///
+    /// From "Watertight Ray/Triangle Intersection", Woop et al. 2013.
+    pub watertight: bool,
+    /// Faces to ignore during intersection.
+    pub cull: CullMode,
+}
+
+/// Faces culled during intersection.
+///
+/// Front faces have a counter-clockwise winding when seen from the ray origin.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CullMode {
+    /// Intersect both sides.
+    #[default]
+    None,
+    /// Ignore back faces.
+    Back,
+    /// Ignore front faces.
+    Front,
+}
+
+/// Result of a ray query performed with [`QueryOptions`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueryResult {
+    /// Number of steps performed.
+    pub steps: u32,
+    /// `true` if the front face of the primitive was hit.
+    ///
+    /// `false` if nothing was hit.
+    pub front_face: bool,
+}
+
+impl QueryOptions {
+    /// Intersect a triangle, updating [`Ray::hit`] if closer.
+    pub(crate) fn intersect_triangle(&self, ray: &mut Ray, triangle: [[f32; 3]; 3], prim: u32) {
+        let culled = match self.cull {
+            CullMode::None => false,
+            CullMode::Back => !front_face(ray, triangle),
+            CullMode::Front => front_face(ray, triangle),
+        };
+        if culled {
+            return;
+        }
+        let hit = if self.watertight {
+            math::intersect_triangle_watertight(ray.origin, ray.dir, triangle)
+        } else {
+            math::intersect_triangle(ray.origin, ray.dir, triangle)
+        };
+        if let Some((t, u, v)) = hit {
+            if t > ray.t_min() && t < ray.hit.t {
+                ray.hit = Intersection { t, u, v, prim };
+            }
+        }
+    }
+}
+
+/// Returns `true` if the ray faces the front of the triangle.
+pub(crate) fn front_face(ray: &Ray, triangle: [[f32; 3]; 3]) -> bool {
+    let [v0, v1, v2] = triangle;
+    let normal = math::cross(math::sub(v1, v0), math::sub(v2, v0));
+    math::dot(normal, ray.dir) < 0.0
+}
+
+/// Child of a wide node, visited by [`traverse_wide`].
+pub(crate) enum WideChild<L> {
+    /// Internal child, with its node index.
+    Internal(u32),
+    /// Leaf child, with the data required to intersect its primitives.
+    Leaf(L),
+}
+
+/// Closest hit traversal of a wide hierarchy, rooted at node `0`.
+///
+/// `children` lists the non-empty children of a node, with their bounds.
+/// Leaf children hit by the ray are intersected right away with `leaf`, which
+/// returns the number of primitive tests, and count as node visits. Internal
+/// children are visited closest first.
+pub(crate) fn traverse_wide<C, I, L, T>(ray: &mut Ray, children: C, mut leaf: L) -> TraversalStats
+where
+    C: Fn(u32) -> I,
+    I: Iterator<Item = (Aabb, WideChild<T>)>,
+    L: FnMut(&mut Ray, T) -> u32,
+{
+    let mut stats = TraversalStats::default();
+    let mut stack = vec![(0, 0.0)];
+    let mut internal = Vec::new();
+    while let Some((id, dist)) = stack.pop() {
+        if dist >= ray.hit.t {
+            continue;
+        }
+        stats.node_visits += 1;
+        internal.clear();
+        for (aabb, child) in children(id) {
+            let dist = math::intersect_aabb(ray.origin, ray.r_d, ray.hit.t, aabb.min, aabb.max);
+            if dist >= crate::INFINITE {
+                continue;
+            }
+            match child {
+                WideChild::Internal(id) => internal.push((id, dist)),
+                WideChild::Leaf(data) => {
+                    stats.node_visits += 1;
+                    stats.leaf_visits += 1;
+                    stats.primitive_tests += leaf(ray, data);
+                }
+            }
+        }
+        // Push the furthest children first to visit the closest ones first.
+        internal.sort_by(|a, b| b.1.total_cmp(&a.1));
+        stack.extend_from_slice(&internal);
+    }
+    stats
+}
@@ -0,0 +1,174 @@
+use crate::{cwbvh, math, Aabb};
+
+/// Structural error reported by [`crate::wald::BVH::validate`] and [`crate::cwbvh::BVH::validate`].
+///
+/// Nodes are identified by their index in the layout node array.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationError {
+    /// Node data can't be read.
+    Layout(cwbvh::LayoutError),
+    /// Child node index out of bounds.
+    InvalidNode { parent: u32, child: u32 },
+    /// Node reached more than once, because of a cycle or a shared child.
+    NodeRevisited { node: u32 },
+    /// Leaf primitive range out of bounds.
+    InvalidPrimitiveRange { node: u32, start: u32, count: u32 },
+    /// Leaf references a primitive that doesn't exist.
+    InvalidPrimitive { node: u32, prim: u32 },
+    /// Child bounds aren't enclosed by the parent bounds.
+    ChildNotEnclosed { parent: u32, child: u32 },
+    /// Leaf bounds don't contain one of the leaf triangles.
+    ///
+    /// With spatial splits, leaf bounds don't overlap the triangle.
+    TriangleNotEnclosed { node: u32, prim: u32 },
+    /// Primitive referenced `count` times by the leaves, instead of once.
+    ///
+    /// With spatial splits, primitive not referenced.
+    PrimitiveReferences { prim: u32, count: u32 },
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Layout(err) => write!(f, "{err}"),
+            Self::InvalidNode { parent, child } => {
+                write!(f, "node {parent} references out of bounds child {child}")
+            }
+            Self::NodeRevisited { node } => write!(f, "node {node} is reached more than once"),
+            Self::InvalidPrimitiveRange { node, start, count } => write!(
+                f,
+                "node {node} references out of bounds primitives {start}..{}",
+                *start as u64 + *count as u64
+            ),
+            Self::InvalidPrimitive { node, prim } => {
+                write!(f, "node {node} references out of bounds primitive {prim}")
+            }
+            Self::ChildNotEnclosed { parent, child } => {
+                write!(f, "node {child} bounds aren't enclosed by parent {parent}")
+            }
+            Self::TriangleNotEnclosed { node, prim } => {
+                write!(f, "node {node} bounds don't contain primitive {prim}")
+            }
+            Self::PrimitiveReferences { prim, count } => {
+                write!(f, "primitive {prim} is referenced {count} times")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Gather validation errors, see [`ValidationError`].
+pub(crate) struct Validator {
+    pub(crate) errors: Vec<ValidationError>,
+    visited: Vec<bool>,
+    references: Vec<u32>,
+    /// Primitives can be referenced by several leaves, and clipped by leaf bounds.
+    spatial_splits: bool,
+}
+
+impl Validator {
+    /// Create a validator.
+    ///
+    /// `spatial_splits` must only be set for BVHs built with spatial splits,
+    /// it relaxes the leaf checks.
+    pub(crate) fn new(node_count: usize, primitive_count: usize, spatial_splits: bool) -> Self {
+        Self {
+            errors: Vec::new(),
+            visited: vec![false; node_count],
+            references: vec![0; primitive_count],
+            spatial_splits,
+        }
+    }
+
+    /// Mark `child` as visited.
+    ///
+    /// Returns `false` if the node is out of bounds or was already visited.
+    pub(crate) fn visit(&mut self, parent: u32, child: u32) -> bool {
+        match self.visited.get_mut(child as usize) {
+            None => {
+                self.errors
+                    .push(ValidationError::InvalidNode { parent, child });
+                false
+            }
+            Some(true) => {
+                self.errors
+                    .push(ValidationError::NodeRevisited { node: child });
+                false
+            }
+            Some(visited) => {
+                *visited = true;
+                true
+            }
+        }
+    }
+
+    /// Check that `inner` is enclosed by `outer`, up to `tolerance`.
+    pub(crate) fn enclosed(
+        &mut self,
+        outer: &Aabb,
+        inner: &Aabb,
+        tolerance: f32,
+        err: ValidationError,
+    ) {
+        let enclosed = (0..3).all(|i| {
+            inner.min[i] >= outer.min[i] - tolerance && inner.max[i] <= outer.max[i] + tolerance
+        });
+        if !enclosed {
+            self.errors.push(err);
+        }
+    }
+
+    /// Check that the leaf `node` bounds contain the triangle of `prim`, and count the reference.
+    ///
+    /// With spatial splits, the bounds only need to overlap the triangle.
+    ///
+    /// `triangle` isn't called if `prim` is out of bounds.
+    pub(crate) fn triangle<T>(
+        &mut self,
+        node: u32,
+        aabb: &Aabb,
+        prim: u32,
+        triangle: T,
+        tolerance: f32,
+    ) where
+        T: FnOnce() -> [[f32; 3]; 3],
+    {
+        let Some(count) = self.references.get_mut(prim as usize) else {
+            self.errors
+                .push(ValidationError::InvalidPrimitive { node, prim });
+            return;
+        };
+        *count += 1;
+        let triangle = triangle();
+        let err = ValidationError::TriangleNotEnclosed { node, prim };
+        if self.spatial_splits {
+            let min = aabb.min.map(|v| v - tolerance);
+            let max = aabb.max.map(|v| v + tolerance);
+            if !math::triangle_aabb_overlap(triangle, min, max) {
+                self.errors.push(err);
+            }
+            return;
+        }
+        let mut bounds = Aabb::empty();
+        triangle.into_iter().for_each(|v| bounds.grow(v));
+        self.enclosed(aabb, &bounds, tolerance, err);
+    }
+
+    /// Report primitives not referenced exactly once, or at all with spatial splits.
+    pub(crate) fn finish(mut self) -> Result<(), Vec<ValidationError>> {
+        for (prim, &count) in self.references.iter().enumerate() {
+            if count == 0 || (count > 1 && !self.spatial_splits) {
+                self.errors.push(ValidationError::PrimitiveReferences {
+                    prim: prim as u32,
+                    count,
+                });
+            }
+        }
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.errors)
+        }
+    }
+}
@@ -113,6 +113,29 @@ mod tests {
         let bvh = cwbvh::BVH::new(primitives.as_slice());
         assert_eq!(bvh.nodes().len(), 1);
         assert_eq!(bvh.nodes()[0].primitives().collect::<Vec<u32>>(), [0, 1]);
+        assert_eq!(bvh.try_nodes(), Ok(bvh.nodes()));
+
+        let children: Vec<cwbvh::Child> = bvh.nodes()[0].children().collect();
+        assert_eq!(children.len(), 8);
+        let leaves: Vec<_> = children
+            .iter()
+            .filter_map(|child| match child {
+                cwbvh::Child::Leaf { primitives, aabb } => Some((primitives.clone(), *aabb)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(leaves.len(), 2);
+        assert_eq!(leaves[0].0, 0..1);
+        assert_eq!(leaves[1].0, 1..2);
+        assert_relative_eq!(leaves[0].1.min.as_slice(), [-2.0, 0.0, -1.0].as_slice());
+        assert_relative_eq!(leaves[1].1.max.as_slice(), [2.0, 1.0, -1.0].as_slice());
+        assert!(children[2..]
+            .iter()
+            .all(|child| *child == cwbvh::Child::Empty));
+        assert_eq!(
+            cwbvh::LayoutError::BlockCount(7).to_string(),
+            "tinybvh CWBVH block count 7 isn't a multiple of 5"
+        );
 
         assert_eq!(
             bvh.primitives(),
@@ -135,6 +158,858 @@ mod tests {
         );
     }
 
+    #[test]
+    fn closest_point() {
+        let triangles = split_triangles();
+        let bvh = wald::BVH::new(&triangles);
+
+        let hit = bvh.closest_point([-1.5, 0.5, 0.0], INFINITE).unwrap();
+        assert_eq!(hit.prim, 0);
+        assert_relative_eq!(hit.distance, 1.0);
+        assert_relative_eq!(hit.point.as_slice(), [-1.5, 0.5, -1.0].as_slice());
+
+        let hit = bvh.closest_point([1.5, -1.0, -1.0], INFINITE).unwrap();
+        assert_eq!(hit.prim, 1);
+        assert_relative_eq!(hit.distance, 1.0);
+        assert_relative_eq!(hit.u, 0.5);
+        assert_relative_eq!(hit.v, 0.5);
+
+        assert!(bvh.closest_point([1.5, -1.0, -1.0], 0.5).is_none());
+    }
+
+    #[test]
+    fn query_overlaps() {
+        let triangles = split_triangles();
+        let bvh = wald::BVH::new(&triangles);
+
+        let mut all: Vec<u32> = bvh
+            .query_aabb(Aabb::new([-3.0, -1.0, -2.0], [3.0, 2.0, 0.0]))
+            .collect();
+        all.sort();
+        assert_eq!(all, [0, 1]);
+
+        let left: Vec<u32> = bvh
+            .query_aabb(Aabb::new([-1.9, 0.1, -1.1], [-1.8, 0.2, -0.9]))
+            .collect();
+        assert_eq!(left, [0]);
+
+        // Inside the left triangle bounds, but outside the triangle.
+        let empty: Vec<u32> = bvh
+            .query_aabb(Aabb::new([-1.2, 0.1, -1.1], [-1.1, 0.2, -0.9]))
+            .collect();
+        assert!(empty.is_empty());
+
+        let right: Vec<u32> = bvh.query_sphere([1.5, -0.5, -1.0], 0.6).collect();
+        assert_eq!(right, [1]);
+        assert_eq!(bvh.query_sphere([1.5, -0.5, -1.0], 0.4).count(), 0);
+    }
+
+    #[test]
+    fn query_frustum() {
+        let triangles = split_triangles();
+        let bvh = wald::BVH::new(&triangles);
+
+        // Box frustum `[x_min, x_max] x [-10, 10] x [-10, 10]`.
+        let frustum = |x_min: f32, x_max: f32| {
+            [
+                [1.0, 0.0, 0.0, -x_min],
+                [-1.0, 0.0, 0.0, x_max],
+                [0.0, 1.0, 0.0, 10.0],
+                [0.0, -1.0, 0.0, 10.0],
+                [0.0, 0.0, 1.0, 10.0],
+                [0.0, 0.0, -1.0, 10.0],
+            ]
+        };
+
+        let mut all = bvh.query_frustum(&frustum(-10.0, 10.0));
+        all.sort();
+        assert_eq!(all, [0, 1]);
+        assert_eq!(bvh.query_frustum(&frustum(0.5, 10.0)), [1]);
+        assert_eq!(bvh.query_frustum(&frustum(-10.0, -1.5)), [0]);
+        assert!(bvh.query_frustum(&frustum(-0.5, 0.5)).is_empty());
+    }
+
+    #[test]
+    fn overlapping_pairs() {
+        let triangles = split_triangles();
+        let bvh = wald::BVH::new(&triangles);
+
+        assert!(wald::overlapping_pairs(&bvh, &bvh, &IDENTITY).is_empty());
+
+        // Move `b` by 4 units along x: its left triangle lands on `a` right triangle.
+        let mut transform = IDENTITY;
+        transform[3][0] = 4.0;
+        let pairs = wald::overlapping_pairs(&bvh, &bvh, &transform);
+        assert_eq!(pairs, [(1, 0)]);
+
+        transform[3][2] = 1.0;
+        assert!(wald::overlapping_pairs(&bvh, &bvh, &transform).is_empty());
+    }
+
+    #[test]
+    fn cone_footprint() {
+        let triangles = split_triangles();
+        let bvh = wald::BVH::new(&triangles);
+
+        let mut cone = Cone::new([-1.5, 0.5, 1.0], [0.0, 0.0, -1.0], 0.1, 0.5);
+        bvh.intersect(&mut cone.ray);
+        assert_relative_eq!(cone.ray.hit.t, 2.0);
+        assert_eq!(cone.ray.hit.prim, 0);
+        assert_relative_eq!(cone.footprint().unwrap(), 0.1 + 4.0 * 0.25_f32.tan());
+
+        // Non-unit directions give the same footprint.
+        let mut cone = Cone::new([-1.5, 0.5, 1.0], [0.0, 0.0, -2.0], 0.1, 0.5);
+        bvh.intersect(&mut cone.ray);
+        assert_relative_eq!(cone.footprint().unwrap(), 0.1 + 4.0 * 0.25_f32.tan());
+
+        let mut cone = Cone {
+            ray: Ray::new([-1.5, 0.5, 1.0], [0.0, 0.0, -2.0]),
+            width: 0.1,
+            spread_angle: 0.5,
+        };
+        bvh.intersect(&mut cone.ray);
+        assert_relative_eq!(cone.ray.hit.t, 1.0);
+        assert_relative_eq!(cone.footprint().unwrap(), 0.1 + 4.0 * 0.25_f32.tan());
+
+        let mut cone = Cone::new([0.0, 0.0, 0.0], [0.0, 0.0, -1.0], 0.1, 0.5);
+        bvh.intersect(&mut cone.ray);
+        assert!(cone.footprint().is_none());
+    }
+
+    #[test]
+    fn motion_blur() {
+        let start = split_triangles();
+        // Move the right triangle 1 unit along x, and 2 units away from the origin.
+        let mut end = split_triangles();
+        for vertex in &mut end[3..] {
+            vertex[0] += 1.0;
+            vertex[2] -= 2.0;
+        }
+        let bvh = motion::BVH::new([&start, &end]);
+        assert_eq!(bvh.keys_count(), 2);
+        assert_relative_eq!(bvh.node_bounds_at(0, 0.5).max[0], 2.5);
+
+        let mut ray = Ray::new([1.75, 0.25, 0.0], [0.0, 0.0, -1.0]);
+        bvh.intersect(&mut ray);
+        assert_relative_eq!(ray.hit.t, 1.0);
+        assert_eq!(ray.hit.prim, 1);
+
+        let mut ray = Ray::with_time([2.25, 0.25, 0.0], [0.0, 0.0, -1.0], 0.5);
+        bvh.intersect(&mut ray);
+        assert_relative_eq!(ray.hit.t, 2.0);
+        assert_eq!(ray.hit.prim, 1);
+
+        let mut ray = Ray::with_time([2.25, 0.25, 0.0], [0.0, 0.0, -1.0], 0.0);
+        bvh.intersect(&mut ray);
+        assert_relative_eq!(ray.hit.t, INFINITE);
+
+        // Left triangle is static.
+        let mut ray = Ray::with_time([-1.5, 0.5, 0.0], [0.0, 0.0, -1.0], 1.0);
+        bvh.intersect(&mut ray);
+        assert_relative_eq!(ray.hit.t, 1.0);
+        assert_eq!(ray.hit.prim, 0);
+    }
+
+    #[test]
+    fn tlas_motion_blur() {
+        let triangles = split_triangles();
+        let blas = wald::BVH::new(&triangles);
+
+        let mut offset = IDENTITY;
+        offset[3][0] = 10.0;
+        let mut moved = offset;
+        moved[3][2] = -2.0;
+        let tlas = tlas::BVH::new(vec![
+            tlas::Instance::new(&blas, IDENTITY),
+            tlas::Instance::moving(&blas, offset, moved),
+        ]);
+        assert_relative_eq!(tlas.nodes()[0].min[2], -3.0);
+
+        let mut ray = Ray::new([-1.5, 0.5, 0.0], [0.0, 0.0, -1.0]);
+        assert_eq!(tlas.intersect_instance(&mut ray), Some(0));
+        assert_relative_eq!(ray.hit.t, 1.0);
+        assert_eq!(ray.hit.prim, 0);
+
+        let mut ray = Ray::with_time([8.5, 0.5, 0.0], [0.0, 0.0, -1.0], 0.0);
+        assert_eq!(tlas.intersect_instance(&mut ray), Some(1));
+        assert_relative_eq!(ray.hit.t, 1.0);
+
+        let mut ray = Ray::with_time([8.5, 0.5, 0.0], [0.0, 0.0, -1.0], 0.5);
+        assert_eq!(tlas.intersect_instance(&mut ray), Some(1));
+        assert_relative_eq!(ray.hit.t, 2.0);
+
+        let mut ray = Ray::with_time([11.5, 0.45, 0.0], [0.0, 0.0, -1.0], 1.0);
+        tlas.intersect(&mut ray);
+        assert_relative_eq!(ray.hit.t, 3.0);
+        assert_eq!(ray.hit.prim, 1);
+
+        // Half turn around y, the instance stays rigid during the motion.
+        let mut turned = IDENTITY;
+        turned[0][0] = -1.0;
+        turned[2][2] = -1.0;
+        let instance = tlas::Instance::moving(&blas, IDENTITY, turned);
+        let transform = instance.transform_at(0.5);
+        for (column, expected) in [[0.0, 0.0, -1.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]]
+            .iter()
+            .enumerate()
+        {
+            for row in 0..3 {
+                assert_relative_eq!(transform[column][row], expected[row], epsilon = 1e-6);
+            }
+        }
+
+        // Quarter turn at mid shutter: both triangles face the x axis.
+        let tlas = tlas::BVH::new(vec![instance]);
+        assert!(tlas.nodes()[0].max[2] >= 2.0);
+        let mut ray = Ray::with_time([-5.0, 0.8, 1.8], [1.0, 0.0, 0.0], 0.5);
+        assert_eq!(tlas.intersect_instance(&mut ray), Some(0));
+        assert_relative_eq!(ray.hit.t, 4.0, epsilon = 1e-5);
+        assert_eq!(ray.hit.prim, 0);
+    }
+
+    #[test]
+    fn layout_quads() {
+        let quads = [
+            [-2.0, 0.0, -1.0, 0.0],
+            [-1.0, 0.0, -1.0, 0.0],
+            [-1.0, 1.0, -1.0, 0.0],
+            [-2.0, 1.0, -1.0, 0.0],
+            [1.0, 0.0, -2.0, 0.0],
+            [2.0, 0.0, -2.0, 0.0],
+            [2.0, 1.0, -2.0, 0.0],
+            [1.0, 1.0, -2.0, 0.0],
+        ];
+        let bvh = quads::BVH::new(&quads);
+
+        // First triangle of the quad.
+        let mut ray = Ray::new([-1.25, 0.25, 0.0], [0.0, 0.0, -1.0]);
+        bvh.intersect(&mut ray);
+        assert_relative_eq!(ray.hit.t, 1.0);
+        assert_eq!(ray.hit.prim, 0);
+        assert_relative_eq!(ray.hit.u, 0.75);
+        assert_relative_eq!(ray.hit.v, 0.25);
+
+        // Second triangle of the quad.
+        let mut ray = Ray::new([1.25, 0.75, 0.0], [0.0, 0.0, -1.0]);
+        bvh.intersect(&mut ray);
+        assert_relative_eq!(ray.hit.t, 2.0);
+        assert_eq!(ray.hit.prim, 1);
+        assert_relative_eq!(ray.hit.u, 0.25);
+        assert_relative_eq!(ray.hit.v, 0.75);
+
+        let mut ray = Ray::new([0.0, 0.5, 0.0], [0.0, 0.0, -1.0]);
+        bvh.intersect(&mut ray);
+        assert_relative_eq!(ray.hit.t, INFINITE);
+    }
+
+    #[test]
+    fn layout_curves() {
+        let segments = [
+            [-1.5, 0.0, -1.0, 0.1],
+            [-1.5, 1.0, -1.0, 0.1],
+            [1.5, 0.0, -2.0, 0.2],
+            [1.5, 1.0, -2.0, 0.1],
+        ];
+        let bvh = curves::BVH::new(&segments);
+
+        let mut ray = Ray::new([-1.5, 0.25, 0.0], [0.0, 0.0, -1.0]);
+        bvh.intersect(&mut ray);
+        assert_relative_eq!(ray.hit.t, 0.9);
+        assert_relative_eq!(ray.hit.u, 0.25);
+        assert_eq!(ray.hit.prim, 0);
+
+        // Rounded cap at the end of the second segment.
+        let mut ray = Ray::new([1.5, 2.0, -2.0], [0.0, -2.0, 0.0]);
+        bvh.intersect(&mut ray);
+        assert_relative_eq!(ray.hit.t, 0.45, epsilon = 1e-6);
+        assert_relative_eq!(ray.hit.u, 1.0);
+        assert_eq!(ray.hit.prim, 1);
+
+        let mut ray = Ray::new([1.5, 0.5, 0.0], [0.0, 0.0, -1.0]);
+        bvh.intersect(&mut ray);
+        assert!(ray.hit.t > 1.8 && ray.hit.t < 1.9);
+
+        let mut ray = Ray::new([0.0, 0.5, 0.0], [0.0, 0.0, -1.0]);
+        bvh.intersect(&mut ray);
+        assert_relative_eq!(ray.hit.t, INFINITE);
+    }
+
+    #[test]
+    fn layout_spheres() {
+        let points = [
+            [-1.5, 0.5, -1.0, 0.5],
+            [1.5, 0.5, -2.0, 0.25],
+            [1.5, 0.5, -4.0, 1.0],
+        ];
+        let bvh = spheres::BVH::new(&points);
+
+        let mut ray = Ray::new([-1.5, 0.5, 0.0], [0.0, 0.0, -1.0]);
+        bvh.intersect(&mut ray);
+        assert_relative_eq!(ray.hit.t, 0.5);
+        assert_eq!(ray.hit.prim, 0);
+        assert_relative_eq!(ray.hit.v, 0.5);
+
+        let mut ray = Ray::new([1.5, 0.5, 0.0], [0.0, 0.0, -1.0]);
+        bvh.intersect(&mut ray);
+        assert_relative_eq!(ray.hit.t, 1.75);
+        assert_eq!(ray.hit.prim, 1);
+
+        // From inside the last sphere.
+        let mut ray = Ray::new([1.5, 0.5, -4.0], [0.0, 1.0, 0.0]);
+        bvh.intersect(&mut ray);
+        assert_relative_eq!(ray.hit.t, 1.0);
+        assert_eq!(ray.hit.prim, 2);
+        assert_relative_eq!(ray.hit.v, 0.0);
+
+        let mut ray = Ray::new([0.0, 0.5, 0.0], [0.0, 0.0, -1.0]);
+        bvh.intersect(&mut ray);
+        assert_relative_eq!(ray.hit.t, INFINITE);
+    }
+
+    #[test]
+    fn watertight() {
+        // Square made of two triangles sharing the diagonal edge.
+        let triangles = [
+            [0.0, 0.0, -1.0, 0.0],
+            [1.0, 0.0, -1.0, 0.0],
+            [1.0, 1.0, -1.0, 0.0],
+            [0.0, 0.0, -1.0, 0.0],
+            [1.0, 1.0, -1.0, 0.0],
+            [0.0, 1.0, -1.0, 0.0],
+        ];
+        let bvh = wald::BVH::new(&triangles);
+        let options = QueryOptions {
+            watertight: true,
+            ..Default::default()
+        };
+
+        // Rays fired exactly at the shared edge, from a skewed origin.
+        for i in 1..100 {
+            let x = i as f32 / 100.0;
+            let origin = [0.3, 0.7, 1.0];
+            let dir = [x - origin[0], x - origin[1], -2.0];
+            let mut ray = Ray::new(origin, dir);
+            bvh.intersect_with(&mut ray, &options);
+            assert_relative_eq!(ray.hit.t, 1.0, epsilon = 1e-5);
+        }
+
+        // Shared vertex.
+        let mut ray = Ray::new([0.5, 0.5, 0.0], [0.5, 0.5, -1.0]);
+        bvh.intersect_with(&mut ray, &options);
+        assert_relative_eq!(ray.hit.t, 1.0);
+
+        let mut ray = Ray::new([-1.5, 0.5, 0.0], [0.0, 0.0, -1.0]);
+        bvh.intersect_with(&mut ray, &options);
+        assert_relative_eq!(ray.hit.t, INFINITE);
+    }
+
+    #[test]
+    fn cull_mode() {
+        let triangles = split_triangles();
+        let wald = wald::BVH::new(&triangles);
+        let cwbvh = cwbvh::BVH::new(&triangles);
+        let options = |cull| QueryOptions {
+            cull,
+            ..Default::default()
+        };
+
+        // Primitives face towards `-z`.
+        let front = Ray::new([-1.5, 0.5, -2.0], [0.0, 0.0, 1.0]);
+        let back = Ray::new([1.5, 0.45, 0.0], [0.0, 0.0, -1.0]);
+        let cases = [
+            (front, CullMode::None, Some(true)),
+            (front, CullMode::Back, Some(true)),
+            (front, CullMode::Front, None),
+            (back, CullMode::None, Some(false)),
+            (back, CullMode::Back, None),
+            (back, CullMode::Front, Some(false)),
+        ];
+        for (ray, cull, expected) in cases {
+            let mut wald_ray = ray;
+            let wald_result = wald.intersect_with(&mut wald_ray, &options(cull));
+            let mut cwbvh_ray = ray;
+            let cwbvh_result = cwbvh.intersect_with(&mut cwbvh_ray, &options(cull));
+            for (ray, result) in [(wald_ray, wald_result), (cwbvh_ray, cwbvh_result)] {
+                match expected {
+                    Some(front_face) => {
+                        assert_relative_eq!(ray.hit.t, 1.0);
+                        assert_eq!(result.front_face, front_face);
+                    }
+                    None => {
+                        assert_relative_eq!(ray.hit.t, INFINITE);
+                        assert!(!result.front_face);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ray_range() {
+        let triangles = split_triangles();
+        let wald = wald::BVH::new(&triangles);
+        let cwbvh = cwbvh::BVH::new(&triangles);
+        let watertight = QueryOptions {
+            watertight: true,
+            ..Default::default()
+        };
+
+        let intersectors: [&dyn Fn(&mut Ray); 4] = [
+            &|ray| {
+                wald.intersect(ray);
+            },
+            &|ray| {
+                wald.intersect_with(ray, &watertight);
+            },
+            &|ray| {
+                cwbvh.intersect_with(ray, &QueryOptions::default());
+            },
+            &|ray| {
+                cwbvh.intersect_with(ray, &watertight);
+            },
+        ];
+        for intersect in intersectors {
+            let mut ray = Ray::with_range([-1.5, 0.5, 0.0], [0.0, 0.0, -1.0], 0.5, 2.0);
+            intersect(&mut ray);
+            assert_relative_eq!(ray.hit.t, 1.0, epsilon = 1e-6);
+            assert_relative_eq!(ray.origin.as_slice(), [-1.5, 0.5, 0.0].as_slice());
+
+            let mut ray = Ray::with_range([-1.5, 0.5, 0.0], [0.0, 0.0, -1.0], 1.5, 10.0);
+            intersect(&mut ray);
+            assert_relative_eq!(ray.hit.t, 10.0);
+
+            let mut ray = Ray::with_range([-1.5, 0.5, 0.0], [0.0, 0.0, -1.0], 0.0, 0.5);
+            intersect(&mut ray);
+            assert_relative_eq!(ray.hit.t, 0.5);
+        }
+
+        // Secondary ray spawned from the hit does not intersect the surface back.
+        let origin = offset_origin([-1.5, 0.5, -1.0], [0.0, 0.0, 1.0]);
+        assert!(origin[2] > -1.0 && origin[2] < -0.99);
+        let mut ray = Ray::new(origin, [0.0, 0.0, 1.0]);
+        wald.intersect(&mut ray);
+        assert_relative_eq!(ray.hit.t, INFINITE);
+    }
+
+    #[test]
+    fn hit_info() {
+        let triangles = split_triangles();
+        let bvh = wald::BVH::new(&triangles);
+
+        let mut ray = Ray::new([1.5, 0.25, 0.0], [0.0, 0.0, -1.0]);
+        assert!(bvh.hit_info(&ray).is_none());
+        bvh.intersect(&mut ray);
+        let info = bvh.hit_info(&ray).unwrap();
+        assert_relative_eq!(info.point.as_slice(), [1.5, 0.25, -1.0].as_slice());
+        assert_relative_eq!(info.normal.as_slice(), [0.0, 0.0, -1.0].as_slice());
+        assert_relative_eq!(info.facing(ray.dir).as_slice(), [0.0, 0.0, 1.0].as_slice());
+        assert_eq!(
+            info.vertices,
+            [[2.0, 1.0, -1.0], [2.0, 0.0, -1.0], [1.0, 0.0, -1.0]]
+        );
+
+        let bvh = cwbvh::BVH::new(&triangles);
+        let mut ray = Ray::new([-1.75, 0.75, 0.0], [0.0, 0.0, -1.0]);
+        bvh.intersect_with(&mut ray, &QueryOptions::default());
+        let info = bvh.hit_info(&ray).unwrap();
+        assert_relative_eq!(info.point.as_slice(), [-1.75, 0.75, -1.0].as_slice());
+    }
+
+    #[test]
+    fn interpolate_attributes() {
+        use pas::slice_attr;
+
+        let vertex = |position: [f32; 4], uv: [f32; 2]| Vertex {
+            position,
+            uv,
+            normal: [0.0, 0.0, 1.0],
+        };
+        let vertices = [
+            vertex([2.0, 1.0, -1.0, 0.0], [1.0, 1.0]),
+            vertex([2.0, 0.0, -1.0, 0.0], [1.0, 0.0]),
+            vertex([1.0, 0.0, -1.0, 0.0], [0.0, 0.0]),
+        ];
+        let bvh = wald::BVH::new(slice_attr!(vertices, [0].position));
+
+        let mut ray = Ray::new([1.75, 0.25, 0.0], [0.0, 0.0, -1.0]);
+        bvh.intersect(&mut ray);
+        let uv: [f32; 2] = interpolate(&ray.hit, slice_attr!(vertices, [0].uv), None);
+        assert_relative_eq!(uv.as_slice(), [0.75, 0.25].as_slice());
+        let normal: [f32; 3] = interpolate(&ray.hit, slice_attr!(vertices, [0].normal), None);
+        assert_relative_eq!(normal.as_slice(), [0.0, 0.0, 1.0].as_slice());
+
+        // Indexed geometry.
+        let uvs = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]];
+        let indices = [2, 1, 0];
+        let uv: [f32; 2] = interpolate(&ray.hit, &uvs, Some(&indices));
+        assert_relative_eq!(uv.as_slice(), [0.75, 0.25].as_slice());
+
+        let weights = [10.0, 20.0, 30.0];
+        let weight: f32 = interpolate(&ray.hit, &weights, None);
+        assert_relative_eq!(weight, 20.0);
+    }
+
+    #[test]
+    fn gpu_buffer() {
+        let triangles = split_triangles();
+        let bvh = wald::BVH::new(&triangles);
+        let buffer = bvh.gpu_buffer();
+
+        let header = buffer.header();
+        assert_eq!(header.nodes_count as usize, bvh.nodes().len());
+        assert_eq!(header.triangles_count as usize, triangles.len() / 3);
+        assert_eq!(buffer.nodes(), bvh.nodes());
+        assert_eq!(buffer.as_bytes().len(), buffer.data().len() * 16);
+        for (triangle, &prim) in buffer.triangles().iter().zip(bvh.indices()) {
+            assert_eq!(triangle.prim, prim);
+            assert_eq!(triangle.vertex_0, triangles[prim as usize * 3][0..3]);
+        }
+
+        let rays = [
+            Ray::new([-1.5, 0.5, 0.0], [0.0, 0.0, -1.0]),
+            Ray::new([1.75, 0.25, 0.0], [0.0, 0.0, -1.0]),
+            Ray::new([0.0, 0.5, 1.0], [-0.5, 0.0, -1.0]),
+            Ray::new([0.0, 0.0, 1.0], [0.0, 0.0, 1.0]),
+        ];
+        for ray in rays {
+            let mut expected = ray;
+            bvh.intersect(&mut expected);
+            let mut ray = ray;
+            buffer.intersect(&mut ray);
+            assert_eq!(ray.hit.prim, expected.hit.prim);
+            assert_relative_eq!(ray.hit.t, expected.hit.t);
+        }
+    }
+
+    #[test]
+    fn gpu_shaders() {
+        let validate = |module: &naga::Module| {
+            let capabilities = naga::valid::Capabilities::all();
+            naga::valid::Validator::new(Default::default(), capabilities)
+                .validate(module)
+                .unwrap();
+        };
+
+        let wgsl = format!(
+            "@group(0) @binding(0) var<storage, read> bvh_data: array<vec4<u32>>;
+            @group(0) @binding(1) var<storage, read_write> result: array<f32>;
+            {}
+            @compute @workgroup_size(1)
+            fn main() {{
+                let ray = BvhRay(vec3<f32>(0.0), 0.0, vec3<f32>(0.0, 0.0, -1.0), 1e30);
+                result[0] = bvh_intersect(ray).t;
+            }}",
+            wald::WGSL_TRAVERSAL
+        );
+        let module = naga::front::wgsl::parse_str(&wgsl)
+            .unwrap_or_else(|err| panic!("{}", err.emit_to_string(&wgsl)));
+        validate(&module);
+
+        let glsl = format!(
+            "#version 450
+            layout(local_size_x = 1) in;
+            layout(std430, binding = 0) readonly buffer BvhBuffer {{ uvec4 bvh_data[]; }};
+            layout(std430, binding = 1) buffer Result {{ float result[]; }};
+            {}
+            void main() {{
+                BvhRay ray = BvhRay(vec3(0.0), 0.0, vec3(0.0, 0.0, -1.0), 1e30);
+                result[0] = bvh_intersect(ray).t;
+            }}",
+            wald::GLSL_TRAVERSAL
+        );
+        let options = naga::front::glsl::Options::from(naga::ShaderStage::Compute);
+        let module = naga::front::glsl::Frontend::default()
+            .parse(&options, &glsl)
+            .unwrap_or_else(|err| panic!("{}", err.emit_to_string(&glsl)));
+        validate(&module);
+    }
+
+    #[test]
+    fn layout_gpu() {
+        let triangles = split_triangles();
+        let wald = wald::BVH::new(&triangles);
+        let expected_nodes = wald.nodes().to_vec();
+        let bvh = gpu::BVH::new(wald);
+
+        let root = &bvh.nodes()[0];
+        assert!(!root.is_leaf());
+        let left = &expected_nodes[expected_nodes[0].left_first as usize];
+        assert_eq!(root.lmin, left.min);
+        assert_eq!(root.lmax, left.max);
+        let leaves = bvh.nodes().iter().filter(|node| node.is_leaf());
+        let count: u32 = leaves.map(|node| node.tri_count).sum();
+        assert_eq!(count as usize, triangles.len() / 3);
+
+        let rays = [
+            Ray::new([-1.5, 0.5, 0.0], [0.0, 0.0, -1.0]),
+            Ray::new([1.75, 0.25, 0.0], [0.0, 0.0, -1.0]),
+            Ray::new([0.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+        ];
+        for ray in rays {
+            let mut expected = ray;
+            bvh.bvh().intersect(&mut expected);
+            let mut ray = ray;
+            bvh.intersect(&mut ray);
+            assert_eq!(ray.hit.prim, expected.hit.prim);
+            assert_relative_eq!(ray.hit.t, expected.hit.t);
+        }
+    }
+
+    #[test]
+    fn layout_bvh4_gpu() {
+        let triangles = split_triangles();
+        let bvh = bvh4_gpu::BVH::new(&triangles);
+
+        let root = bvh.node(0);
+        assert_relative_eq!(root.min.as_slice(), [-2.0, 0.0, -1.0].as_slice());
+        let mut count = 0;
+        for (offset, node) in bvh.nodes() {
+            for slot in (0..4).filter(|&slot| node.is_leaf(slot)) {
+                let aabb = node.child_aabb(slot);
+                let (tri_count, start) = node.leaf_triangles(slot);
+                for i in 0..tri_count {
+                    let triangle = bvh.triangle(offset + start + i * 3);
+                    for vertex in triangle.vertices() {
+                        let grown = aabb.union(&Aabb::new(vertex, vertex));
+                        assert_relative_eq!(grown.min.as_slice(), aabb.min.as_slice());
+                        assert_relative_eq!(grown.max.as_slice(), aabb.max.as_slice());
+                    }
+                }
+                count += tri_count;
+            }
+        }
+        assert_eq!(count as usize, triangles.len() / 3);
+
+        let options = QueryOptions {
+            watertight: true,
+            ..Default::default()
+        };
+        let rays = [
+            Ray::new([-1.5, 0.5, 0.0], [0.0, 0.0, -1.0]),
+            Ray::new([1.75, 0.25, 0.0], [0.0, 0.0, -1.0]),
+            Ray::new([0.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+        ];
+        for ray in rays {
+            let mut expected = ray;
+            bvh.intersect(&mut expected);
+            let mut ray = ray;
+            bvh.intersect_with(&mut ray, &options);
+            assert_eq!(ray.hit.prim, expected.hit.prim);
+            assert_relative_eq!(ray.hit.t, expected.hit.t);
+        }
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        nodes: Vec<(u32, Aabb, bool)>,
+        leaves: Vec<Vec<u32>>,
+    }
+
+    impl Visitor for Recorder {
+        fn enter_node(&mut self, depth: u32, aabb: &Aabb, is_leaf: bool) -> bool {
+            self.nodes.push((depth, *aabb, is_leaf));
+            true
+        }
+
+        fn leaf(&mut self, primitives: &[u32]) {
+            self.leaves.push(primitives.to_vec());
+        }
+    }
+
+    #[test]
+    fn walk() {
+        let triangles = split_triangles();
+
+        let bvh = wald::BVH::new(&triangles);
+        let mut recorder = Recorder::default();
+        bvh.walk(&mut recorder);
+        assert_eq!(recorder.nodes[0].0, 0);
+        assert!(!recorder.nodes[0].2);
+        assert_relative_eq!(
+            recorder.nodes[0].1.min.as_slice(),
+            [-2.0, 0.0, -1.0].as_slice()
+        );
+        assert_relative_eq!(
+            recorder.nodes[0].1.max.as_slice(),
+            [2.0, 1.0, -1.0].as_slice()
+        );
+        let mut primitives: Vec<u32> = recorder.leaves.concat();
+        primitives.sort();
+        assert_eq!(primitives, [0, 1]);
+
+        let bvh = cwbvh::BVH::new(&triangles);
+        let mut recorder = Recorder::default();
+        bvh.walk(&mut recorder);
+        let depths: Vec<(u32, bool)> = recorder.nodes.iter().map(|n| (n.0, n.2)).collect();
+        assert_eq!(depths, [(0, false), (1, true), (1, true)]);
+        assert_eq!(recorder.leaves, [vec![0], vec![1]]);
+        assert_relative_eq!(
+            recorder.nodes[0].1.min.as_slice(),
+            [-2.0, 0.0, -1.0].as_slice()
+        );
+    }
+
+    #[test]
+    fn stats() {
+        let triangles = split_triangles();
+
+        let bvh = wald::BVH::new(&triangles);
+        let stats = bvh.stats();
+        assert_eq!(stats.node_count, 3);
+        assert_eq!(stats.leaf_count, 2);
+        assert_eq!(stats.max_depth(), 1);
+        assert_eq!(stats.depth_histogram, [0, 2]);
+        assert_eq!(stats.leaf_size_histogram, [0, 2]);
+        assert_eq!(stats.memory, bvh.nodes().len() * 32 + 2 * 4);
+        // Root area is 8, and leaves area is 2.
+        assert_relative_eq!(stats.sah_cost, 1.5);
+        assert_eq!(stats.epo_cost, None);
+        assert_relative_eq!(stats.average_overlap, 0.0);
+        let epo_stats = bvh.stats_with_epo();
+        assert_eq!(epo_stats.epo_cost, Some(0.0));
+        assert_eq!(epo_stats.sah_cost, stats.sah_cost);
+
+        // Disjoint triangles with overlapping leaves: the second triangle covers
+        // an area of 1 in the first leaf, out of a total area of 16.
+        let overlapping = vec![
+            [0.0, 0.0, -1.0, 0.0],
+            [4.0, 0.0, -1.0, 0.0],
+            [0.0, 4.0, -1.0, 0.0],
+            [3.0, 3.0, -1.0, 0.0],
+            [7.0, 3.0, -1.0, 0.0],
+            [3.0, 7.0, -1.0, 0.0],
+        ];
+        let overlapping_bvh = wald::BVH::new(&overlapping);
+        assert_eq!(overlapping_bvh.stats().leaf_count, 2);
+        let epo_cost = overlapping_bvh.stats_with_epo().epo_cost.unwrap();
+        assert_relative_eq!(epo_cost, 1.0 / 16.0);
+
+        let cwbvh = cwbvh::BVH::new(&triangles);
+        let cwbvh_stats = cwbvh.stats();
+        assert_eq!(cwbvh_stats.node_count, 3);
+        assert_eq!(cwbvh_stats.depth_histogram, [0, 2]);
+        assert_eq!(cwbvh_stats.memory, 80 + 2 * 48);
+        assert_relative_eq!(cwbvh_stats.sah_cost, 1.5, epsilon = 0.1);
+
+        let gpu = gpu::BVH::new(bvh);
+        assert_eq!(gpu.stats().memory, 3 * 64 + 2 * 4);
+        assert_eq!(gpu.stats().depth_histogram, stats.depth_histogram);
+    }
+
+    #[test]
+    fn traversal_stats() {
+        let triangles = split_triangles();
+        let bvh = wald::BVH::new(&triangles);
+        let cwbvh = cwbvh::BVH::new(&triangles);
+
+        let mut ray = Ray::new([-1.5, 0.5, 0.0], [0.0, 0.0, -1.0]);
+        let stats = bvh.intersect_instrumented(&mut ray);
+        assert_eq!(ray.hit.prim, 0);
+        assert_relative_eq!(ray.hit.t, 1.0);
+        assert_eq!(
+            stats,
+            TraversalStats {
+                node_visits: 2,
+                leaf_visits: 1,
+                primitive_tests: 1
+            }
+        );
+        assert_eq!(stats.cost(), 3);
+
+        // Same hierarchy, leaf slots count as nodes.
+        let mut ray = Ray::new([-1.5, 0.5, 0.0], [0.0, 0.0, -1.0]);
+        assert_eq!(cwbvh.intersect_instrumented(&mut ray), stats);
+        assert_eq!(ray.hit.prim, 0);
+
+        let mut ray = Ray::new([0.0, 0.5, 0.0], [0.0, 0.0, -1.0]);
+        let stats = bvh.intersect_instrumented(&mut ray);
+        assert_eq!(ray.hit.t, 1e30);
+        assert_eq!(stats.leaf_visits, 0);
+
+        let camera = Camera::new([0.0, 0.5, 3.0], [0.0, 0.5, 0.0]);
+        let map = heatmap(&bvh, &camera, 32, 16);
+        assert_eq!(map.pixels.len(), 32 * 16);
+        assert_eq!(map.max_cost(), 3);
+        let image = map.to_rgba8(None);
+        assert_eq!(image.len(), 32 * 16 * 4);
+        let hottest = map.pixels.iter().position(|p| p.cost() == 3).unwrap();
+        assert_eq!(image[hottest * 4..hottest * 4 + 4], [255, 0, 0, 255]);
+
+        // Degenerate cameras still produce valid rays.
+        let looking_down = Camera::new([0.0, 3.0, 0.0], [0.0, 0.0, 0.0]);
+        let ray = looking_down.ray(0, 0, 4, 4);
+        assert!(ray.dir.iter().all(|d| d.is_finite()));
+        assert!(ray.dir[1] < 0.0);
+        let ray = Camera::new([0.0, 0.5, 3.0], [0.0, 0.5, 3.0]).ray(2, 2, 4, 4);
+        assert!(ray.dir.iter().all(|d| d.is_finite()));
+        assert!(ray.dir[2] < 0.0);
+    }
+
+    #[test]
+    fn validate() {
+        let triangles = split_triangles();
+        assert_eq!(wald::BVH::new(&triangles).validate(), Ok(()));
+        assert_eq!(cwbvh::BVH::new(&triangles).validate(), Ok(()));
+
+        // Long crossing slivers, prone to spatial splits.
+        let slivers: Vec<[f32; 4]> = (0..8)
+            .flat_map(|i| {
+                let (y, z) = (i as f32, (i % 2) as f32 * 0.25);
+                let dir = if i % 2 == 0 { 1.0 } else { -1.0 };
+                [
+                    [-8.0 * dir, y, z, 0.0],
+                    [8.0 * dir, 8.0 - y, z, 0.0],
+                    [8.0 * dir, 8.1 - y, z, 0.0],
+                ]
+            })
+            .collect();
+        assert!(!wald::BVH::new(&slivers).spatial_splits());
+        assert!(wald::BVH::new_hq(&slivers).spatial_splits());
+        assert_eq!(wald::BVH::new_hq(&slivers).validate(), Ok(()));
+        assert_eq!(cwbvh::BVH::new_hq(&slivers).validate(), Ok(()));
+
+        let err = ValidationError::PrimitiveReferences { prim: 3, count: 2 };
+        assert_eq!(err.to_string(), "primitive 3 is referenced 2 times");
+
+        let err = ValidationError::InvalidPrimitiveRange {
+            node: 1,
+            start: u32::MAX,
+            count: 2,
+        };
+        assert_eq!(
+            err.to_string(),
+            "node 1 references out of bounds primitives 4294967295..4294967297"
+        );
+    }
+
+    #[test]
+    fn export_obj() {
+        let triangles = split_triangles();
+        let count = |obj: &str, prefix: &str| obj.lines().filter(|l| l.starts_with(prefix)).count();
+
+        let bvh = wald::BVH::new(&triangles);
+        let mut obj = Vec::new();
+        bvh.export_obj(&mut obj, 0..=0).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        assert_eq!(count(&obj, "o "), 1);
+        assert!(obj.contains("o depth_0"));
+        assert_eq!(count(&obj, "v "), 8);
+        assert_eq!(count(&obj, "f "), 6);
+
+        let mut obj = Vec::new();
+        bvh.export_obj(&mut obj, ..).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        assert_eq!(count(&obj, "v "), 8 * bvh.stats().node_count as usize);
+
+        let bvh = cwbvh::BVH::new(&triangles);
+        let mut obj = Vec::new();
+        bvh.export_obj(&mut obj, 1..).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        assert!(!obj.contains("o depth_0"));
+        assert!(obj.contains("o depth_1"));
+        assert_eq!(count(&obj, "v "), 8 * (bvh.stats().node_count as usize - 1));
+    }
+
     #[test]
     fn capture() {
         let mut triangles = split_triangles();